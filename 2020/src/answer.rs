@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt};
+
+/// The result of solving one part of a puzzle.
+///
+/// Integers that fit in an `i64` are always stored as `Integer`, so two
+/// answers for the same value compare equal whichever type they came from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Answer {
+    Integer(i64),
+    BigInteger(#[serde(with = "big_integer")] i128),
+    String(String),
+    Failure(String),
+}
+
+impl Answer {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Answer::Failure(_))
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::BigInteger(n) => write!(f, "{}", n),
+            Answer::String(s) => write!(f, "{}", s),
+            Answer::Failure(reason) => write!(f, "no answer: {}", reason),
+        }
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    match i64::try_from(n) {
+                        Ok(n) => Answer::Integer(n),
+                        Err(_) => match i128::try_from(n) {
+                            Ok(n) => Answer::BigInteger(n),
+                            Err(_) => Answer::Failure(format!("{} is too large", n)),
+                        },
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::String(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::String(String::from(s))
+    }
+}
+
+impl<T, E> From<Result<T, E>> for Answer
+where
+    T: Into<Answer>,
+    E: fmt::Display,
+{
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(answer) => answer.into(),
+            Err(e) => Answer::Failure(e.to_string()),
+        }
+    }
+}
+
+mod big_integer {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(n: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(n)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SolveError;
+
+    #[test]
+    fn it_converts_results() {
+        assert_eq!(Answer::Integer(514579), Answer::from(514579usize));
+        assert_eq!(Answer::Integer(-3), Answer::from(-3isize));
+        assert_eq!(Answer::BigInteger(u64::MAX.into()), Answer::from(u64::MAX));
+        assert_eq!(
+            Answer::Failure(String::from("day 1: no two entries sum to 2020")),
+            Answer::from(Err::<i32, _>(SolveError::new(
+                1,
+                "no two entries sum to 2020"
+            )))
+        );
+    }
+
+    #[test]
+    fn it_displays_answers() {
+        assert_eq!("127", Answer::Integer(127).to_string());
+        assert_eq!("18446744073709551615", Answer::from(u64::MAX).to_string());
+        assert_eq!(
+            "no answer: oops",
+            Answer::Failure(String::from("oops")).to_string()
+        );
+    }
+
+    #[test]
+    fn it_serializes_answers() {
+        let answers = vec![
+            Answer::Integer(127),
+            Answer::BigInteger(18446744073709551615),
+            Answer::String(String::from("abc")),
+            Answer::Failure(String::from("oops")),
+        ];
+        let json = serde_json::to_string(&answers).unwrap();
+
+        assert_eq!(
+            r#"[{"type":"integer","value":127},{"type":"big_integer","value":"18446744073709551615"},{"type":"string","value":"abc"},{"type":"failure","value":"oops"}]"#,
+            json
+        );
+        assert_eq!(answers, serde_json::from_str::<Vec<Answer>>(&json).unwrap());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{answer::Answer, runner::Outcome};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Day {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    part1: Option<Answer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    part2: Option<Answer>,
+}
+
+impl Day {
+    fn part(&self, part: u32) -> Option<&Answer> {
+        match part {
+            1 => self.part1.as_ref(),
+            2 => self.part2.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn part_mut(&mut self, part: u32) -> Option<&mut Option<Answer>> {
+        match part {
+            1 => Some(&mut self.part1),
+            2 => Some(&mut self.part2),
+            _ => None,
+        }
+    }
+}
+
+/// Known-correct answers for one set of puzzle inputs, keyed by day and part.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Answers(BTreeMap<u32, Day>);
+
+impl Answers {
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .with_context(|| format!("parsing {} failed", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(e) => Err(e).with_context(|| format!("reading {} failed", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(path, data + "\n").with_context(|| format!("writing {} failed", path.display()))
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<&Answer> {
+        self.0.get(&day).and_then(|d| d.part(part))
+    }
+
+    pub fn insert(&mut self, day: u32, part: u32, answer: Answer) {
+        if let Some(slot) = self.0.entry(day).or_default().part_mut(part) {
+            *slot = Some(answer);
+        }
+    }
+
+    pub fn check(&self, outcome: &Outcome) -> Status {
+        match (self.get(outcome.day, outcome.part), &outcome.answer) {
+            (None, _) => Status::Missing,
+            (Some(expected), actual) if expected == actual && !actual.is_failure() => Status::Pass,
+            _ => Status::Fail,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Fail,
+    Missing,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::time::Duration;
+
+    fn outcome(day: u32, part: u32, answer: Answer) -> Outcome {
+        Outcome {
+            day,
+            part,
+            answer,
+            generator: Duration::default(),
+            solver: Duration::default(),
+        }
+    }
+
+    #[test]
+    fn it_parses_answers() {
+        let data = indoc! {r#"
+            {
+              "1": {
+                "part1": {
+                  "type": "integer",
+                  "value": 514579
+                },
+                "part2": {
+                  "type": "integer",
+                  "value": 241861950
+                }
+              },
+              "9": {
+                "part1": {
+                  "type": "integer",
+                  "value": 127
+                }
+              }
+            }
+        "#};
+        let answers: Answers = serde_json::from_str(data).unwrap();
+
+        assert_eq!(Some(&Answer::Integer(514579)), answers.get(1, 1));
+        assert_eq!(Some(&Answer::Integer(241861950)), answers.get(1, 2));
+        assert_eq!(Some(&Answer::Integer(127)), answers.get(9, 1));
+        assert_eq!(None, answers.get(9, 2));
+        assert_eq!(
+            data.trim_end(),
+            serde_json::to_string_pretty(&answers).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_checks_outcomes() {
+        let mut answers = Answers::default();
+        answers.insert(1, 1, Answer::Integer(514579));
+        answers.insert(1, 2, Answer::Integer(241861950));
+
+        assert_eq!(Status::Pass, answers.check(&outcome(1, 1, 514579.into())));
+        assert_eq!(Status::Fail, answers.check(&outcome(1, 2, 514579.into())));
+        assert_eq!(
+            Status::Fail,
+            answers.check(&outcome(1, 2, Answer::Failure(String::from("failed"))))
+        );
+        assert_eq!(Status::Missing, answers.check(&outcome(2, 1, 2.into())));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::grid::{Grid, Position};
+use std::{collections::HashMap, hash::Hash};
+
+/// Why an automaton stopped stepping.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Halt {
+    /// The generation at `step` maps onto itself.
+    FixedPoint { step: usize },
+    /// The generation at `start + period` would repeat the one at `start`.
+    Cycle { start: usize, period: usize },
+    /// The step limit was reached before the automaton settled.
+    Limit { step: usize },
+}
+
+/// Repeatedly applies `rule` to a state, keeping every generation seen so
+/// far so that fixed points and cycles can be detected.
+pub struct Automaton<S, F> {
+    history: Vec<S>,
+    seen: HashMap<S, usize>,
+    rule: F,
+    limit: Option<usize>,
+    halt: Option<Halt>,
+}
+
+impl<S, F> Automaton<S, F>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> S,
+{
+    pub fn new(initial: S, rule: F) -> Self {
+        let mut seen = HashMap::new();
+        seen.insert(initial.clone(), 0);
+
+        Automaton {
+            history: vec![initial],
+            seen,
+            rule,
+            limit: None,
+            halt: None,
+        }
+    }
+
+    /// Stops after at most `limit` steps.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// The number of steps taken so far.
+    pub fn steps(&self) -> usize {
+        self.history.len() - 1
+    }
+
+    pub fn current(&self) -> &S {
+        &self.history[self.steps()]
+    }
+
+    /// Every generation so far, starting with the initial state. None of
+    /// them repeat.
+    pub fn history(&self) -> &[S] {
+        &self.history
+    }
+
+    pub fn halt(&self) -> Option<Halt> {
+        self.halt
+    }
+
+    /// Steps until the automaton reaches a fixed point, enters a cycle or
+    /// hits the step limit.
+    pub fn run(&mut self) -> Halt {
+        loop {
+            if let Some(halt) = self.halt {
+                return halt;
+            }
+
+            self.step();
+        }
+    }
+
+    /// Takes a single step, returning the new generation, or `None` once the
+    /// automaton has halted.
+    pub fn step(&mut self) -> Option<&S> {
+        if self.halt.is_some() {
+            return None;
+        }
+
+        let step = self.steps();
+        if self.limit.is_some_and(|limit| step >= limit) {
+            self.halt = Some(Halt::Limit { step });
+            return None;
+        }
+
+        let next = (self.rule)(&self.history[step]);
+
+        if let Some(&start) = self.seen.get(&next) {
+            self.halt = Some(if start == step {
+                Halt::FixedPoint { step }
+            } else {
+                Halt::Cycle {
+                    start,
+                    period: step + 1 - start,
+                }
+            });
+            return None;
+        }
+
+        self.seen.insert(next.clone(), step + 1);
+        self.history.push(next);
+
+        Some(self.current())
+    }
+}
+
+impl<S, F> Iterator for Automaton<S, F>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> S,
+{
+    type Item = S;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step().cloned()
+    }
+}
+
+/// An automaton over a grid where every cell is updated at once by `rule`,
+/// which sees the previous generation and so can use any neighbourhood.
+pub fn cellular<T, R>(grid: Grid<T>, rule: R) -> Automaton<Grid<T>, impl FnMut(&Grid<T>) -> Grid<T>>
+where
+    T: Clone + Eq + Hash,
+    R: Fn(&Grid<T>, Position, &T) -> T,
+{
+    Automaton::new(grid, move |grid: &Grid<T>| {
+        grid.map(|position, cell| rule(grid, position, cell))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_fixed_points() {
+        let mut automaton = Automaton::new(10, |&n: &u32| n / 2);
+
+        assert_eq!(Halt::FixedPoint { step: 4 }, automaton.run());
+        assert_eq!(&[10, 5, 2, 1, 0], automaton.history());
+        assert_eq!(4, automaton.steps());
+        assert_eq!(&0, automaton.current());
+        assert_eq!(None, automaton.step());
+    }
+
+    #[test]
+    fn it_finds_cycles() {
+        let mut automaton = Automaton::new(0, |&n: &u32| if n < 3 { n + 1 } else { 1 });
+
+        assert_eq!(
+            Halt::Cycle {
+                start: 1,
+                period: 3
+            },
+            automaton.run()
+        );
+        assert_eq!(&[0, 1, 2, 3], automaton.history());
+    }
+
+    #[test]
+    fn it_stops_at_the_limit() {
+        let mut automaton = Automaton::new(0, |&n: &u32| n + 1).with_limit(5);
+
+        assert_eq!(vec![1, 2, 3, 4, 5], automaton.by_ref().collect::<Vec<_>>());
+        assert_eq!(Some(Halt::Limit { step: 5 }), automaton.halt());
+    }
+
+    #[test]
+    fn it_steps_grids() {
+        let blinker = Grid::new(
+            3,
+            3,
+            vec![false, true, false, false, true, false, false, true, false],
+        );
+        let mut automaton = cellular(blinker.clone(), |grid, position, &alive| {
+            let neighbours = grid.neighbours8(position).filter(|(_, &n)| n).count();
+            neighbours == 3 || alive && neighbours == 2
+        });
+
+        assert_eq!(
+            Halt::Cycle {
+                start: 0,
+                period: 2
+            },
+            automaton.run()
+        );
+        assert_eq!(blinker.transpose(), automaton.history()[1]);
+    }
+}
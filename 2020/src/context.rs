@@ -0,0 +1,70 @@
+use std::{cell::OnceCell, fmt, ops::Deref};
+
+/// Parsed input for a day along with a value shared between its parts.
+///
+/// The shared value is computed from the input the first time any part asks
+/// for it, so a later part can build on an earlier part's result instead of
+/// hardcoding it.
+pub struct Context<I, S> {
+    input: I,
+    compute: fn(&I) -> S,
+    shared: OnceCell<S>,
+}
+
+impl<I, S> Context<I, S> {
+    pub fn new(input: I, compute: fn(&I) -> S) -> Self {
+        Context {
+            input,
+            compute,
+            shared: OnceCell::new(),
+        }
+    }
+
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    pub fn shared(&self) -> &S {
+        self.shared.get_or_init(|| (self.compute)(&self.input))
+    }
+}
+
+impl<I, S> Deref for Context<I, S> {
+    type Target = I;
+
+    fn deref(&self) -> &Self::Target {
+        &self.input
+    }
+}
+
+impl<I: fmt::Debug, S: fmt::Debug> fmt::Debug for Context<I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("input", &self.input)
+            .field("shared", &self.shared.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn it_computes_the_shared_value_once() {
+        thread_local! {
+            static CALLS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        let context = Context::new(vec![1, 2, 3], |input| {
+            CALLS.with(|calls| calls.set(calls.get() + 1));
+            input.iter().sum::<i32>()
+        });
+
+        assert_eq!(6, *context.shared());
+        assert_eq!(6, *context.shared());
+        assert_eq!(1, CALLS.with(Cell::get));
+        assert_eq!(&vec![1, 2, 3], context.input());
+    }
+}
@@ -0,0 +1,253 @@
+use serde::Serialize;
+
+/// An entry from the expense report and where it was found.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Entry {
+    /// The position of the entry in the report, 0 for the first line.
+    pub index: usize,
+    pub value: i32,
+}
+
+fn sorted(entries: &[i32]) -> Vec<Entry> {
+    let mut sorted: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| Entry { index, value })
+        .collect();
+    sorted.sort_unstable_by_key(|entry| (entry.value, entry.index));
+    sorted
+}
+
+/// Finds `k` different entries that add up to `target`, in report order.
+///
+/// Entries are never reused, but equal values on different lines are
+/// separate entries. The entries are sorted once, then all but the last two
+/// are fixed in turn and the last two are found with a pair of pointers
+/// closing in from either end, so this takes O(n^(k-1)) time for k >= 2.
+/// The answer is the same every time: the one whose values come first when
+/// sorted.
+pub fn k_sum(entries: &[i32], k: usize, target: i64) -> Option<Vec<Entry>> {
+    let mut first = None;
+
+    search(&sorted(entries), k, target, &mut Vec::new(), &mut |found| {
+        first = Some(found.to_vec());
+        true
+    });
+
+    first.map(|mut first: Vec<_>| {
+        first.sort_unstable();
+        first
+    })
+}
+
+/// Every way of picking `k` different entries that add up to `target`, each
+/// in report order and sorted by the lines they come from.
+pub fn k_sum_all(entries: &[i32], k: usize, target: i64) -> Vec<Vec<Entry>> {
+    let mut all = Vec::new();
+
+    search(&sorted(entries), k, target, &mut Vec::new(), &mut |found| {
+        let mut found = found.to_vec();
+        found.sort_unstable();
+        all.push(found);
+        false
+    });
+
+    all.sort_unstable();
+    all
+}
+
+/// Passes every combination of `k` entries from `sorted` that adds up to
+/// `target` to `found`, along with the entries already `chosen`, until
+/// `found` returns true to stop.
+fn search(
+    sorted: &[Entry],
+    k: usize,
+    target: i64,
+    chosen: &mut Vec<Entry>,
+    found: &mut dyn FnMut(&[Entry]) -> bool,
+) -> bool {
+    let mut emit = |chosen: &mut Vec<Entry>, entries: &[Entry]| {
+        chosen.extend_from_slice(entries);
+        let stop = found(chosen);
+        chosen.truncate(chosen.len() - entries.len());
+        stop
+    };
+
+    if k == 0 {
+        return target == 0 && emit(chosen, &[]);
+    }
+
+    if sorted.len() < k {
+        return false;
+    }
+
+    let sum = |entries: &[Entry]| entries.iter().map(|e| i64::from(e.value)).sum::<i64>();
+    if sum(&sorted[..k]) > target || sum(&sorted[sorted.len() - k..]) < target {
+        return false;
+    }
+
+    match k {
+        1 => {
+            let start = sorted.partition_point(|e| i64::from(e.value) < target);
+            sorted[start..]
+                .iter()
+                .take_while(|e| i64::from(e.value) == target)
+                .any(|&e| emit(chosen, &[e]))
+        }
+        2 => {
+            let (mut lo, mut hi) = (0, sorted.len() - 1);
+
+            while lo < hi {
+                let (low, high) = (sorted[lo].value, sorted[hi].value);
+                let pair = i64::from(low) + i64::from(high);
+
+                if pair < target {
+                    lo += 1;
+                } else if pair > target {
+                    hi -= 1;
+                } else if low == high {
+                    // Everything in between has the same value, so any two
+                    // of them will do.
+                    return (lo..hi)
+                        .any(|i| (i + 1..=hi).any(|j| emit(chosen, &[sorted[i], sorted[j]])));
+                } else {
+                    let lows = lo + sorted[lo..].iter().take_while(|e| e.value == low).count();
+                    let highs = hi + 1
+                        - sorted[..=hi]
+                            .iter()
+                            .rev()
+                            .take_while(|e| e.value == high)
+                            .count();
+
+                    if (lo..lows)
+                        .any(|i| (highs..=hi).any(|j| emit(chosen, &[sorted[i], sorted[j]])))
+                    {
+                        return true;
+                    }
+
+                    lo = lows;
+                    hi = highs - 1;
+                }
+            }
+
+            false
+        }
+        _ => {
+            for i in 0..=sorted.len() - k {
+                chosen.push(sorted[i]);
+                let stop = search(
+                    &sorted[i + 1..],
+                    k - 1,
+                    target - i64::from(sorted[i].value),
+                    chosen,
+                    found,
+                );
+                chosen.pop();
+
+                if stop {
+                    return true;
+                }
+            }
+
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    fn values(entries: &[Entry]) -> Vec<i32> {
+        entries.iter().map(|entry| entry.value).collect()
+    }
+
+    #[test]
+    fn it_treats_entries_as_a_multiset() {
+        assert_eq!(
+            Some(vec![
+                Entry {
+                    index: 1,
+                    value: 1010
+                },
+                Entry {
+                    index: 3,
+                    value: 1010
+                }
+            ]),
+            k_sum(&[5, 1010, 7, 1010], 2, 2020)
+        );
+        assert_eq!(None, k_sum(&[5, 1010, 7], 2, 2020));
+        assert_eq!(None, k_sum(&[2020], 2, 2020));
+        assert_eq!(Some(vec![]), k_sum(&[1, 2], 0, 0));
+        assert_eq!(Some(vec![3]), k_sum(&[1, 2, 3], 1, 3).map(|e| values(&e)));
+    }
+
+    #[test]
+    fn it_finds_every_combination() {
+        let all = k_sum_all(&[1010, 5, 1010, 2015, 1010, 5], 2, 2020);
+
+        assert_eq!(
+            vec![
+                vec![(0, 1010), (2, 1010)],
+                vec![(0, 1010), (4, 1010)],
+                vec![(1, 5), (3, 2015)],
+                vec![(2, 1010), (4, 1010)],
+                vec![(3, 2015), (5, 5)],
+            ],
+            all.iter()
+                .map(|entries| entries.iter().map(|e| (e.index, e.value)).collect())
+                .collect::<Vec<Vec<_>>>()
+        );
+        assert_eq!(
+            Some(&all[2]),
+            k_sum(&[1010, 5, 1010, 2015, 1010, 5], 2, 2020).as_ref()
+        );
+    }
+
+    #[test]
+    fn it_agrees_with_brute_force() {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound) as i32
+        };
+
+        for _ in 0..200 {
+            let entries: Vec<_> = (0..12).map(|_| next(40) - 10).collect();
+            let k = next(5) as usize;
+            let target = i64::from(next(80) - 20);
+
+            let found = k_sum(&entries, k, target);
+            let expected: Vec<Vec<_>> = (0..entries.len())
+                .combinations(k)
+                .filter(|c| c.iter().map(|&i| i64::from(entries[i])).sum::<i64>() == target)
+                .collect();
+
+            assert_eq!(
+                expected,
+                k_sum_all(&entries, k, target)
+                    .iter()
+                    .map(|c| c.iter().map(|e| e.index).collect::<Vec<_>>())
+                    .collect::<Vec<_>>(),
+                "{:?} {} {}",
+                entries,
+                k,
+                target
+            );
+            assert_eq!(!expected.is_empty(), found.is_some());
+
+            if let Some(found) = found {
+                assert_eq!(k, found.iter().map(|e| e.index).unique().count());
+                assert!(found.iter().all(|e| entries[e.index] == e.value));
+                assert_eq!(
+                    target,
+                    found.iter().map(|e| i64::from(e.value)).sum::<i64>()
+                );
+            }
+        }
+    }
+}
@@ -1,32 +1,66 @@
-use anyhow::Result;
-use std::{collections::HashSet, num::ParseIntError};
+mod ksum;
 
-fn find_pair(input: &HashSet<i32>, total: i32) -> Option<i32> {
-    input.iter().find_map(|x| {
-        let y = total - x;
-        input.get(&y).map(|_| x * y)
+pub use ksum::{k_sum, k_sum_all, Entry};
+
+use crate::{
+    answer::Answer,
+    error::{ParseError, SolveError},
+    runner::{self, Outcome},
+};
+
+/// The product of the entries' values, unless it doesn't fit in an `i64`.
+fn product(entries: &[Entry]) -> Option<i64> {
+    entries.iter().try_fold(1i64, |product, entry| {
+        product.checked_mul(i64::from(entry.value))
     })
 }
 
-fn find_triple(input: &HashSet<i32>, total: i32) -> Option<i32> {
-    input
+/// The product of `k` different entries that add up to 2020.
+fn solve(input: &[i32], k: usize) -> Result<i64, SolveError> {
+    let entries = k_sum(input, k, 2020)
+        .ok_or_else(|| SolveError::new(1, format!("no {} entries sum to 2020", k)))?;
+
+    product(&entries)
+        .ok_or_else(|| SolveError::new(1, format!("the product of the {} entries is too large", k)))
+}
+
+/// Spells out where a combination of entries came from, like
+/// `1721 (line 1) + 299 (line 4) = 2020, product 514579`, or `product too
+/// large` if it doesn't fit in an `i64`.
+pub fn describe(entries: &[Entry]) -> String {
+    let terms: Vec<_> = entries
         .iter()
-        .find_map(|x| find_pair(input, total - x).map(|result| x * result))
+        .map(|entry| format!("{} (line {})", entry.value, entry.index + 1))
+        .collect();
+    let sum: i64 = entries.iter().map(|entry| i64::from(entry.value)).sum();
+    let product = product(entries).map_or_else(|| String::from("too large"), |p| p.to_string());
+
+    format!("{} = {}, product {}", terms.join(" + "), sum, product)
 }
 
 #[aoc_generator(day1)]
-pub fn generator(data: &str) -> Result<HashSet<i32>, ParseIntError> {
-    data.lines().map(|line| line.parse::<i32>()).collect()
+pub fn generator(data: &str) -> Result<Vec<i32>, ParseError> {
+    data.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.parse::<i32>()
+                .map_err(|e| ParseError::at(1, line, line, e.to_string()).offset(i))
+        })
+        .collect()
 }
 
 #[aoc(day1, part1)]
-pub fn part1(input: &HashSet<i32>) -> Option<i32> {
-    find_pair(input, 2020)
+pub fn part1(input: &[i32]) -> Answer {
+    solve(input, 2).into()
 }
 
 #[aoc(day1, part2)]
-pub fn part2(input: &HashSet<i32>) -> Option<i32> {
-    find_triple(&input, 2020)
+pub fn part2(input: &[i32]) -> Answer {
+    solve(input, 3).into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(1, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -47,13 +81,7 @@ mod tests {
 
         let input = generator(data).unwrap();
 
-        assert_eq!(
-            [1721, 979, 366, 299, 675, 1456]
-                .iter()
-                .cloned()
-                .collect::<HashSet<i32>>(),
-            input
-        )
+        assert_eq!(vec![1721, 979, 366, 299, 675, 1456], input)
     }
 
     #[test]
@@ -68,7 +96,7 @@ mod tests {
         "};
 
         let input = generator(data).unwrap();
-        let result = find_pair(&input, 2020).unwrap();
+        let result = solve(&input, 2).unwrap();
 
         assert_eq!(result, 514579)
     }
@@ -85,8 +113,59 @@ mod tests {
         "};
 
         let input = generator(data).unwrap();
-        let result = find_triple(&input, 2020).unwrap();
+        let result = solve(&input, 3).unwrap();
 
         assert_eq!(result, 241861950)
     }
+
+    #[test]
+    fn it_reports_products_that_overflow() {
+        assert_eq!(
+            Err(SolveError::new(
+                1,
+                "the product of the 3 entries is too large"
+            )),
+            solve(&[-1_000_000_000, 1_000_000_000, 2020], 3)
+        );
+        assert_eq!(
+            Ok(-19_960_000_000_000),
+            solve(&[1_000_000, -998_000, 20], 3)
+        );
+    }
+
+    #[test]
+    fn it_describes_entries() {
+        let input = generator("1721\n979\n366\n299\n675\n1456").unwrap();
+
+        assert_eq!(
+            "1721 (line 1) + 299 (line 4) = 2020, product 514579",
+            describe(&k_sum(&input, 2, 2020).unwrap())
+        );
+        assert_eq!(
+            vec!["979 (line 2) + 366 (line 3) + 675 (line 5) = 2020, product 241861950"],
+            k_sum_all(&input, 3, 2020)
+                .iter()
+                .map(|entries| describe(entries))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            "1000000 (line 1) + 1000000 (line 2) + 1000000 (line 3) + 1000000 (line 4) \
+             = 4000000, product too large",
+            describe(&k_sum(&[1_000_000; 4], 4, 4_000_000).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_entries() {
+        let data = indoc! {"
+            1721
+            97x
+        "};
+
+        assert_eq!(
+            ParseError::new(1, 2, 1, "97x", "invalid digit found in string"),
+            generator(data).unwrap_err()
+        )
+    }
 }
@@ -1,69 +1,245 @@
-use anyhow::{Context, Result};
+mod policy;
+mod report;
+
+pub use policy::{
+    rule, AtMost, AuthenticationSystem, CorporatePolicy, Exactly, Forbidden, LetterSet, Pattern,
+    PolicyRule, Verdict, RULES,
+};
+pub use report::{report, to_csv, ReportRow};
+
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    runner::{self, Outcome},
+};
+use anyhow::Result;
 use parse_display::{Display, FromStr};
-use std::str::FromStr;
+use rayon::prelude::*;
+use std::{io::BufRead, ops::Range, str::FromStr};
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Display, Debug, FromStr, PartialEq)]
 #[display("{left}-{right} {letter}")]
-struct PasswordPolicy {
-    left: usize,
-    right: usize,
-    letter: char,
+pub struct PasswordPolicy {
+    pub left: usize,
+    pub right: usize,
+    pub letter: char,
 }
 
-impl PasswordPolicy {
-    fn validate_corporate_policy(&self, password: &str) -> bool {
-        let character_count = password.matches(self.letter).count();
+/// A password and its policy.
+#[derive(Display, Debug, PartialEq)]
+#[display("{policy}: {password}")]
+pub struct PasswordEntry {
+    pub policy: PasswordPolicy,
+    pub password: String,
+    /// The bytes of the grapheme clusters at the policy's two positions, if
+    /// the password has them.
+    positions: [Option<Range<usize>>; 2],
+    /// Why the policy's numbers aren't both positions in the password, found
+    /// while parsing so it can point at the numbers as they were written.
+    position_error: Option<ParseError>,
+}
 
-        character_count >= self.left && character_count <= self.right
+impl PasswordEntry {
+    pub fn satisfies(&self, rule: &dyn PolicyRule) -> bool {
+        rule.check(self)
     }
 
-    fn validate_authentication_system(&self, password: &str) -> bool {
-        let first_index = self.left - 1;
-        let second_index = self.right - 1;
+    /// The user-perceived characters at the policy's two 1-based positions,
+    /// if the password has them.
+    pub fn positions(&self) -> [Option<&str>; 2] {
+        let [left, right] = &self.positions;
+        let at = |range: &Option<Range<usize>>| range.clone().map(|range| &self.password[range]);
+        [at(left), at(right)]
+    }
 
-        (password.chars().nth(first_index) == Some(self.letter))
-            ^ (password.chars().nth(second_index) == Some(self.letter))
+    /// Checks that both of the policy's numbers are positions in the
+    /// password, for rules that read them that way. Errors are on the first
+    /// line, for the caller to offset.
+    pub fn check_positions(&self) -> Result<(), ParseError> {
+        match &self.position_error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
     }
 }
 
-#[derive(Display, Debug, FromStr, PartialEq)]
-#[display("{policy}: {password}")]
-struct PasswordEntry {
-    policy: PasswordPolicy,
-    password: String,
-}
+impl FromStr for PasswordEntry {
+    type Err = ParseError;
 
-impl PasswordEntry {
-    fn matches_corporate_policy(&self) -> bool {
-        self.policy.validate_corporate_policy(&self.password)
-    }
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |text: &str| ParseError::at(2, line, text, "expected an entry like `1-3 a: abcde`");
+
+        let (policy_text, password) = line.split_once(": ").ok_or_else(|| invalid(line))?;
+        let (left_text, rest) = policy_text
+            .split_once('-')
+            .ok_or_else(|| invalid(policy_text))?;
+        let (right_text, letter_text) = rest.split_once(' ').ok_or_else(|| invalid(rest))?;
 
-    fn matches_authentication_system(&self) -> bool {
-        self.policy.validate_authentication_system(&self.password)
+        let number = |text: &str| {
+            text.parse::<usize>()
+                .map_err(|e| ParseError::at(2, line, text, format!("invalid number: {}", e)))
+        };
+        let mut letters = letter_text.chars();
+        let letter = match (letters.next(), letters.next()) {
+            (Some(letter), None) => letter,
+            _ => {
+                return Err(ParseError::at(
+                    2,
+                    line,
+                    letter_text,
+                    "expected a single letter",
+                ))
+            }
+        };
+        let policy = PasswordPolicy {
+            left: number(left_text)?,
+            right: number(right_text)?,
+            letter,
+        };
+
+        let mut positions = [None, None];
+        for (i, (start, grapheme)) in password.grapheme_indices(true).enumerate() {
+            for (slot, &position) in positions.iter_mut().zip(&[policy.left, policy.right]) {
+                if position == i + 1 {
+                    *slot = Some(start..start + grapheme.len());
+                }
+            }
+
+            if i + 1 >= policy.left.max(policy.right) {
+                break;
+            }
+        }
+
+        let position_error = [(policy.left, left_text), (policy.right, right_text)]
+            .iter()
+            .zip(&positions)
+            .find_map(|(&(position, text), range)| {
+                if position == 0 {
+                    Some(ParseError::at(2, line, text, "positions start at 1"))
+                } else if range.is_none() {
+                    Some(ParseError::at(
+                        2,
+                        line,
+                        text,
+                        format!("position {} is past the end of the password", position),
+                    ))
+                } else {
+                    None
+                }
+            });
+
+        Ok(PasswordEntry {
+            policy,
+            password: password.to_owned(),
+            positions,
+            position_error,
+        })
     }
 }
 
-#[aoc_generator(day2)]
-fn generator(data: &str) -> Result<Vec<PasswordEntry>> {
+/// Parses a password database, one entry per line.
+///
+/// The policies' numbers aren't checked against the passwords, since most
+/// rules don't read them as positions. See [`check_positions`].
+pub fn load(data: &str) -> Result<Vec<PasswordEntry>, ParseError> {
     data.lines()
-        .map(|line| PasswordEntry::from_str(line).with_context(|| "parsing input failed"))
+        .enumerate()
+        .map(|(i, line)| PasswordEntry::from_str(line).map_err(|e| e.offset(i)))
         .collect()
 }
 
-#[aoc(day2, part1)]
-fn part1(input: &[PasswordEntry]) -> usize {
-    input
+/// Checks the positions of every entry in a database, for rules that read
+/// the policies' numbers as positions.
+pub fn check_positions(entries: &[PasswordEntry]) -> Result<(), ParseError> {
+    entries
         .iter()
-        .filter(|entry| entry.matches_corporate_policy())
-        .count()
+        .enumerate()
+        .try_for_each(|(i, entry)| entry.check_positions().map_err(|e| e.offset(i)))
+}
+
+/// The number of entries, and how many of them meet their policy under each
+/// of the rules. The database is read a block of lines at a time and each
+/// block is checked in parallel, so it never has to fit in memory.
+///
+/// Positions are only checked if one of the rules reads them.
+pub fn count_valid_streaming(
+    input: impl BufRead,
+    rules: &[&dyn PolicyRule],
+) -> Result<(usize, Vec<usize>)> {
+    count_valid_in_blocks(input, rules, 1 << 16)
+}
+
+fn count_valid_in_blocks(
+    input: impl BufRead,
+    rules: &[&dyn PolicyRule],
+    block_size: usize,
+) -> Result<(usize, Vec<usize>)> {
+    let mut counts = vec![0; rules.len()];
+    let positions = rules.iter().any(|rule| rule.uses_positions());
+    let mut lines = input.lines();
+    let mut offset = 0;
+
+    loop {
+        let block = lines
+            .by_ref()
+            .take(block_size)
+            .collect::<Result<Vec<_>, _>>()?;
+        if block.is_empty() {
+            return Ok((offset, counts));
+        }
+
+        let block_counts = block
+            .par_iter()
+            .enumerate()
+            .map(|(i, line)| -> Result<Vec<usize>, ParseError> {
+                let entry = PasswordEntry::from_str(line).map_err(|e| e.offset(offset + i))?;
+                if positions {
+                    entry.check_positions().map_err(|e| e.offset(offset + i))?;
+                }
+                Ok(rules
+                    .iter()
+                    .map(|rule| usize::from(entry.satisfies(*rule)))
+                    .collect())
+            })
+            .try_reduce(
+                || vec![0; rules.len()],
+                |a, b| Ok(a.iter().zip(&b).map(|(a, b)| a + b).collect()),
+            )?;
+
+        for (count, block_count) in counts.iter_mut().zip(block_counts) {
+            *count += block_count;
+        }
+
+        offset += block.len();
+    }
+}
+
+#[aoc_generator(day2)]
+fn generator(data: &str) -> Result<Vec<PasswordEntry>, ParseError> {
+    load(data)
+}
+
+/// How many entries in the database meet their policy under `rule`.
+pub fn count_valid(input: &[PasswordEntry], rule: &dyn PolicyRule) -> usize {
+    input.iter().filter(|entry| entry.satisfies(rule)).count()
+}
+
+#[aoc(day2, part1)]
+fn part1(input: &[PasswordEntry]) -> Answer {
+    count_valid(input, &CorporatePolicy).into()
 }
 
 #[aoc(day2, part2)]
-fn part2(input: &[PasswordEntry]) -> usize {
-    input
-        .iter()
-        .filter(|entry| entry.matches_authentication_system())
-        .count()
+fn part2(input: &[PasswordEntry]) -> Answer {
+    check_positions(input)
+        .map(|()| count_valid(input, &AuthenticationSystem))
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(2, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -92,16 +268,15 @@ mod tests {
         let entry = PasswordEntry::from_str(data).unwrap();
 
         assert_eq!(
-            PasswordEntry {
-                policy: PasswordPolicy {
-                    left: 1,
-                    right: 3,
-                    letter: 'a'
-                },
-                password: String::from("abcde")
+            PasswordPolicy {
+                left: 1,
+                right: 3,
+                letter: 'a'
             },
-            entry
-        )
+            entry.policy
+        );
+        assert_eq!("abcde", entry.password);
+        assert_eq!([Some("a"), Some("c")], entry.positions());
     }
 
     #[test]
@@ -109,17 +284,17 @@ mod tests {
         let data = "1-3 a: abcde";
         let entry = PasswordEntry::from_str(data).unwrap();
 
-        assert_eq!(true, entry.matches_corporate_policy());
+        assert!(entry.satisfies(&CorporatePolicy));
 
         let data = "1-3 b: cdefg";
         let entry = PasswordEntry::from_str(data).unwrap();
 
-        assert_eq!(false, entry.matches_corporate_policy());
+        assert!(!entry.satisfies(&CorporatePolicy));
 
         let data = "2-9 c: ccccccccc";
         let entry = PasswordEntry::from_str(data).unwrap();
 
-        assert_eq!(true, entry.matches_corporate_policy());
+        assert!(entry.satisfies(&CorporatePolicy));
     }
 
     #[test]
@@ -127,17 +302,17 @@ mod tests {
         let data = "1-3 a: abcde";
         let entry = PasswordEntry::from_str(data).unwrap();
 
-        assert_eq!(true, entry.matches_authentication_system());
+        assert!(entry.satisfies(&AuthenticationSystem));
 
         let data = "1-3 b: cdefg";
         let entry = PasswordEntry::from_str(data).unwrap();
 
-        assert_eq!(false, entry.matches_authentication_system());
+        assert!(!entry.satisfies(&AuthenticationSystem));
 
         let data = "2-9 c: ccccccccc";
         let entry = PasswordEntry::from_str(data).unwrap();
 
-        assert_eq!(false, entry.matches_authentication_system());
+        assert!(!entry.satisfies(&AuthenticationSystem));
     }
 
     #[test]
@@ -158,7 +333,9 @@ mod tests {
                         right: 3,
                         letter: 'a'
                     },
-                    password: String::from("abcde")
+                    password: String::from("abcde"),
+                    positions: [Some(0..1), Some(2..3)],
+                    position_error: None
                 },
                 PasswordEntry {
                     policy: PasswordPolicy {
@@ -166,7 +343,9 @@ mod tests {
                         right: 3,
                         letter: 'b'
                     },
-                    password: String::from("cdefg")
+                    password: String::from("cdefg"),
+                    positions: [Some(0..1), Some(2..3)],
+                    position_error: None
                 },
                 PasswordEntry {
                     policy: PasswordPolicy {
@@ -174,10 +353,164 @@ mod tests {
                         right: 9,
                         letter: 'c'
                     },
-                    password: String::from("ccccccccc")
+                    password: String::from("ccccccccc"),
+                    positions: [Some(1..2), Some(8..9)],
+                    position_error: None
                 }
             ],
             input
         )
     }
+
+    #[test]
+    fn it_reports_invalid_entries() {
+        let data = indoc! {"
+            1-3 a: abcde
+            1-3 b cdefg
+        "};
+
+        assert_eq!(
+            ParseError::new(
+                2,
+                2,
+                1,
+                "1-3 b cdefg",
+                "expected an entry like `1-3 a: abcde`"
+            ),
+            generator(data).unwrap_err()
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                2,
+                1,
+                3,
+                "x",
+                "invalid number: invalid digit found in string"
+            )),
+            PasswordEntry::from_str("1-x a: abcde")
+        );
+        assert_eq!(
+            Err(ParseError::new(2, 1, 5, "ab", "expected a single letter")),
+            PasswordEntry::from_str("1-3 ab: abcde")
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                2,
+                1,
+                1,
+                "1 a",
+                "expected an entry like `1-3 a: abcde`"
+            )),
+            PasswordEntry::from_str("1 a: abcde")
+        );
+    }
+
+    #[test]
+    fn it_handles_a_zero_position() {
+        let data = "0-1 a: abcde";
+        let entry = PasswordEntry::from_str(data).unwrap();
+
+        assert!(entry.satisfies(&AuthenticationSystem));
+        assert!(entry.satisfies(&CorporatePolicy));
+        assert_eq!(
+            Err(ParseError::new(2, 1, 1, "0", "positions start at 1")),
+            entry.check_positions()
+        );
+
+        assert_eq!(
+            Err(ParseError::new(2, 1, 1, "00", "positions start at 1")),
+            PasswordEntry::from_str("00-1 a: abcde")
+                .unwrap()
+                .check_positions()
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                2,
+                1,
+                4,
+                "0012",
+                "position 12 is past the end of the password"
+            )),
+            PasswordEntry::from_str("01-0012 a: abcde")
+                .unwrap()
+                .check_positions()
+        );
+    }
+
+    #[test]
+    fn it_only_needs_positions_in_range_for_the_authentication_system() {
+        let input = generator("1-3 a: abcde\n1-12 a: abcde").unwrap();
+
+        assert_eq!(Answer::Integer(2), part1(&input));
+        assert_eq!(2, count_valid(&input, &CorporatePolicy));
+        assert_eq!(
+            Answer::Failure(String::from(
+                "day 2, line 2, column 3: position 12 is past the end of the password: \"12\""
+            )),
+            part2(&input)
+        );
+
+        assert_eq!(
+            Err(ParseError::new(2, 1, 1, "0", "positions start at 1")),
+            PasswordEntry::from_str("0-1 a: abcde")
+                .unwrap()
+                .check_positions()
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                2,
+                2,
+                1,
+                "6",
+                "position 6 is past the end of the password"
+            )),
+            check_positions(&generator("1-3 a: abcde\n6-2 a: abcde").unwrap())
+        );
+
+        let data = "1-3 a: abcde\n1-12 a: abcde\n";
+        assert_eq!(
+            (2, vec![2]),
+            count_valid_streaming(data.as_bytes(), &[&CorporatePolicy]).unwrap()
+        );
+        assert_eq!(
+            "day 2, line 2, column 3: position 12 is past the end of the password: \"12\"",
+            count_valid_streaming(data.as_bytes(), &[&CorporatePolicy, &AuthenticationSystem])
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn it_counts_positions_in_grapheme_clusters() {
+        let entry = PasswordEntry::from_str("2-3 a: \u{1f1ec}\u{1f1e7}aa").unwrap();
+        assert_eq!([Some("a"), Some("a")], entry.positions());
+        assert!(!entry.satisfies(&AuthenticationSystem));
+
+        let entry = PasswordEntry::from_str("1-2 \u{e9}: e\u{301}\u{e9}").unwrap();
+        assert_eq!([Some("e\u{301}"), Some("\u{e9}")], entry.positions());
+        assert!(entry.satisfies(&AuthenticationSystem));
+    }
+
+    #[test]
+    fn it_counts_a_stream_in_parallel() {
+        let data = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n".repeat(100);
+        let rules: [&dyn PolicyRule; 2] = [&CorporatePolicy, &AuthenticationSystem];
+
+        assert_eq!(
+            (300, vec![200, 100]),
+            count_valid_streaming(data.as_bytes(), &rules).unwrap()
+        );
+        assert_eq!(
+            (300, vec![200, 100]),
+            count_valid_in_blocks(data.as_bytes(), &rules, 7).unwrap()
+        );
+
+        let data = format!("{}1-3 b cdefg\n", data);
+        assert_eq!(
+            "day 2, line 301, column 1: expected an entry like `1-3 a: abcde`: \"1-3 b cdefg\"",
+            count_valid_in_blocks(data.as_bytes(), &rules, 7)
+                .unwrap_err()
+                .to_string()
+        );
+    }
 }
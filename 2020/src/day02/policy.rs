@@ -0,0 +1,471 @@
+use super::{PasswordEntry, PasswordPolicy};
+use regex::Regex;
+use std::fmt;
+
+/// One way of reading a policy like `1-3 a` and deciding whether a password
+/// meets it.
+///
+/// Rules display as the name they are selected by with [`rule`].
+///
+/// Rules are shared between threads when a database is checked in parallel.
+pub trait PolicyRule: fmt::Display + Send + Sync {
+    /// Whether the password meets the policy. Called for every line when
+    /// counting, so it shouldn't allocate.
+    fn check(&self, entry: &PasswordEntry) -> bool;
+
+    /// What the rule looked at in the password and why it passes or fails,
+    /// as [`PolicyRule::check`] decides.
+    fn explain(&self, entry: &PasswordEntry) -> Verdict;
+
+    /// Whether the rule reads the policy's numbers as positions in the
+    /// password, which should then be checked with
+    /// [`PasswordEntry::check_positions`].
+    fn uses_positions(&self) -> bool {
+        false
+    }
+}
+
+/// The outcome of checking a password, for people to read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Verdict {
+    pub valid: bool,
+    /// The letter count or letters at the policy's positions.
+    pub observed: String,
+    pub reason: String,
+}
+
+/// How many times the policy's letter appears in the password.
+fn letter_count(entry: &PasswordEntry) -> usize {
+    entry.password.matches(entry.policy.letter).count()
+}
+
+fn in_range(policy: &PasswordPolicy, count: usize) -> bool {
+    (policy.left..=policy.right).contains(&count)
+}
+
+/// Explains a count against the policy's range.
+fn explain_range(policy: &PasswordPolicy, letters: &str, count: usize) -> Verdict {
+    let bound = if count < policy.left {
+        format!("fewer than {}", policy.left)
+    } else if count > policy.right {
+        format!("more than {}", policy.right)
+    } else {
+        format!("within {}-{}", policy.left, policy.right)
+    };
+
+    Verdict {
+        valid: in_range(policy, count),
+        observed: format!("{}: {}", letters, count),
+        reason: format!("{} `{}` is {}", count, letters, bound),
+    }
+}
+
+/// The letter appears between `left` and `right` times, inclusive.
+pub struct CorporatePolicy;
+
+impl PolicyRule for CorporatePolicy {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        in_range(&entry.policy, letter_count(entry))
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        let letter = entry.policy.letter.to_string();
+        explain_range(&entry.policy, &letter, letter_count(entry))
+    }
+}
+
+impl fmt::Display for CorporatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "corporate")
+    }
+}
+
+/// The letter is at exactly one of the 1-based positions `left` and `right`,
+/// counted in grapheme clusters. A position the password doesn't have never
+/// holds the letter.
+pub struct AuthenticationSystem;
+
+impl AuthenticationSystem {
+    /// Whether the characters at each of the policy's positions are its letter.
+    fn matches(entry: &PasswordEntry) -> [bool; 2] {
+        let mut buffer = [0; 4];
+        let letter: &str = entry.policy.letter.encode_utf8(&mut buffer);
+        let [left, right] = entry.positions();
+
+        [left == Some(letter), right == Some(letter)]
+    }
+}
+
+impl PolicyRule for AuthenticationSystem {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        let [left, right] = Self::matches(entry);
+        left ^ right
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        let policy = &entry.policy;
+        let [left, right] = entry.positions();
+
+        let matches = Self::matches(entry);
+        let reason = match matches {
+            [true, false] => format!("only position {} is `{}`", policy.left, policy.letter),
+            [false, true] => format!("only position {} is `{}`", policy.right, policy.letter),
+            [true, true] => format!(
+                "both positions {} and {} are `{}`",
+                policy.left, policy.right, policy.letter
+            ),
+            [false, false] => format!(
+                "neither position {} nor {} is `{}`",
+                policy.left, policy.right, policy.letter
+            ),
+        };
+
+        Verdict {
+            valid: self.check(entry),
+            observed: format!(
+                "{}: {}, {}: {}",
+                policy.left,
+                left.unwrap_or("none"),
+                policy.right,
+                right.unwrap_or("none")
+            ),
+            reason,
+        }
+    }
+
+    fn uses_positions(&self) -> bool {
+        true
+    }
+}
+
+impl fmt::Display for AuthenticationSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "authentication")
+    }
+}
+
+/// The letter appears exactly this many times.
+pub struct Exactly(pub usize);
+
+impl PolicyRule for Exactly {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        letter_count(entry) == self.0
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        let policy = &entry.policy;
+        let count = letter_count(entry);
+
+        Verdict {
+            valid: self.check(entry),
+            observed: format!("{}: {}", policy.letter, count),
+            reason: format!("{} `{}`, expected exactly {}", count, policy.letter, self.0),
+        }
+    }
+}
+
+impl fmt::Display for Exactly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exactly:{}", self.0)
+    }
+}
+
+/// The letter appears no more than this many times.
+pub struct AtMost(pub usize);
+
+impl PolicyRule for AtMost {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        letter_count(entry) <= self.0
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        let policy = &entry.policy;
+        let count = letter_count(entry);
+
+        Verdict {
+            valid: self.check(entry),
+            observed: format!("{}: {}", policy.letter, count),
+            reason: format!("{} `{}`, expected at most {}", count, policy.letter, self.0),
+        }
+    }
+}
+
+impl fmt::Display for AtMost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at-most:{}", self.0)
+    }
+}
+
+/// The letter doesn't appear at all, either the given one or the policy's.
+pub struct Forbidden(pub Option<char>);
+
+impl Forbidden {
+    fn letter(&self, entry: &PasswordEntry) -> char {
+        self.0.unwrap_or(entry.policy.letter)
+    }
+}
+
+impl PolicyRule for Forbidden {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        !entry.password.contains(self.letter(entry))
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        let letter = self.letter(entry);
+        let count = entry.password.matches(letter).count();
+
+        Verdict {
+            valid: self.check(entry),
+            observed: format!("{}: {}", letter, count),
+            reason: if count == 0 {
+                format!("`{}` doesn't appear", letter)
+            } else {
+                format!("`{}` appears {} times", letter, count)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(letter) => write!(f, "forbidden:{}", letter),
+            None => write!(f, "forbidden"),
+        }
+    }
+}
+
+/// Letters from the set appear between `left` and `right` times between
+/// them, ignoring the policy's own letter.
+pub struct LetterSet(pub String);
+
+impl LetterSet {
+    fn count(&self, entry: &PasswordEntry) -> usize {
+        entry.password.matches(|c| self.0.contains(c)).count()
+    }
+}
+
+impl PolicyRule for LetterSet {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        in_range(&entry.policy, self.count(entry))
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        explain_range(&entry.policy, &self.0, self.count(entry))
+    }
+}
+
+impl fmt::Display for LetterSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "letters:{}", self.0)
+    }
+}
+
+/// The password matches a regular expression, ignoring the policy.
+pub struct Pattern(pub Regex);
+
+impl PolicyRule for Pattern {
+    fn check(&self, entry: &PasswordEntry) -> bool {
+        self.0.is_match(&entry.password)
+    }
+
+    fn explain(&self, entry: &PasswordEntry) -> Verdict {
+        let valid = self.check(entry);
+
+        Verdict {
+            valid,
+            observed: String::from(if valid { "match" } else { "no match" }),
+            reason: format!(
+                "{} `{}`",
+                if valid { "matches" } else { "doesn't match" },
+                self.0
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "regex:{}", self.0)
+    }
+}
+
+/// The names [`rule`] accepts.
+pub const RULES: &[&str] = &[
+    "corporate",
+    "authentication",
+    "exactly:N",
+    "at-most:N",
+    "forbidden[:LETTER]",
+    "letters:SET",
+    "regex:PATTERN",
+];
+
+/// Looks up a rule by name, with any argument after a `:`, like `exactly:2`.
+pub fn rule(name: &str) -> Result<Box<dyn PolicyRule>, String> {
+    let (name, argument) = match name.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (name, None),
+    };
+
+    let number = |argument: Option<&str>| {
+        argument
+            .ok_or_else(|| format!("`{}` needs a number, like `{}:2`", name, name))?
+            .parse::<usize>()
+            .map_err(|e| format!("invalid number for `{}`: {}", name, e))
+    };
+
+    Ok(match (name, argument) {
+        ("corporate", None) => Box::new(CorporatePolicy),
+        ("authentication", None) => Box::new(AuthenticationSystem),
+        ("exactly", _) => Box::new(Exactly(number(argument)?)),
+        ("at-most", _) => Box::new(AtMost(number(argument)?)),
+        ("forbidden", None) => Box::new(Forbidden(None)),
+        ("forbidden", Some(letter)) => {
+            let mut chars = letter.chars();
+            match (chars.next(), chars.next()) {
+                (Some(letter), None) => Box::new(Forbidden(Some(letter))),
+                _ => return Err(format!("expected a single letter, not `{}`", letter)),
+            }
+        }
+        ("letters", Some(letters)) if !letters.is_empty() => {
+            Box::new(LetterSet(letters.to_owned()))
+        }
+        ("letters", _) => return Err(String::from("`letters` needs a set, like `letters:abc`")),
+        ("regex", Some(pattern)) => {
+            Box::new(Pattern(Regex::new(pattern).map_err(|e| e.to_string())?))
+        }
+        ("regex", None) => return Err(String::from("`regex` needs a pattern, like `regex:^a`")),
+        ("corporate", Some(_)) | ("authentication", Some(_)) => {
+            return Err(format!("`{}` doesn't take an argument", name))
+        }
+        _ => {
+            return Err(format!(
+                "unknown policy `{}`, expected one of {}",
+                name,
+                RULES.join(", ")
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn check(name: &str, entry: &str) -> bool {
+        rule(name)
+            .unwrap()
+            .check(&PasswordEntry::from_str(entry).unwrap())
+    }
+
+    #[test]
+    fn it_checks_other_rules() {
+        assert!(!check("exactly:2", "1-3 a: banana"));
+        assert!(check("exactly:3", "1-3 a: banana"));
+        assert!(check("at-most:3", "1-3 a: banana"));
+        assert!(!check("at-most:2", "1-3 a: banana"));
+        assert!(check("forbidden", "1-3 z: banana"));
+        assert!(!check("forbidden:n", "1-3 z: banana"));
+        assert!(check("letters:nb", "2-3 z: banana"));
+        assert!(!check("letters:nab", "2-3 z: banana"));
+        assert!(check("regex:^b(an)+a$", "1-3 z: banana"));
+        assert!(!check("regex:^a", "1-3 z: banana"));
+    }
+
+    fn explain(name: &str, entry: &str) -> Verdict {
+        rule(name)
+            .unwrap()
+            .explain(&PasswordEntry::from_str(entry).unwrap())
+    }
+
+    #[test]
+    fn it_explains_verdicts() {
+        assert_eq!(
+            Verdict {
+                valid: false,
+                observed: String::from("b: 0"),
+                reason: String::from("0 `b` is fewer than 1"),
+            },
+            explain("corporate", "1-3 b: cdefg")
+        );
+        assert_eq!(
+            "1 `a` is within 1-3",
+            explain("corporate", "1-3 a: abcde").reason
+        );
+        assert_eq!(
+            Verdict {
+                valid: false,
+                observed: String::from("2: c, 9: c"),
+                reason: String::from("both positions 2 and 9 are `c`"),
+            },
+            explain("authentication", "2-9 c: ccccccccc")
+        );
+        assert_eq!(
+            "1: a, 5: e",
+            explain("authentication", "1-5 a: abcde").observed
+        );
+        assert_eq!(
+            "2 `nb` is more than 1",
+            explain("letters:nb", "1-1 z: bnx").reason
+        );
+        assert_eq!(
+            "`n` appears 2 times",
+            explain("forbidden:n", "1-3 z: banana").reason
+        );
+    }
+
+    #[test]
+    fn it_checks_and_explains_alike() {
+        for name in &[
+            "corporate",
+            "authentication",
+            "exactly:2",
+            "at-most:1",
+            "forbidden",
+            "letters:bn",
+            "regex:^b",
+        ] {
+            for entry in &["1-3 a: banana", "2-4 n: banana", "1-2 z: abc", "0-1 b: b"] {
+                assert_eq!(
+                    check(name, entry),
+                    explain(name, entry).valid,
+                    "{} on {}",
+                    name,
+                    entry
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_selects_rules_by_name() {
+        for name in &[
+            "corporate",
+            "authentication",
+            "exactly:2",
+            "at-most:0",
+            "forbidden",
+            "forbidden:x",
+            "letters:xyz",
+            "regex:^a+$",
+        ] {
+            assert_eq!(*name, rule(name).unwrap().to_string());
+        }
+
+        let error = |name| rule(name).err().unwrap();
+        assert_eq!(
+            "`exactly` needs a number, like `exactly:2`",
+            error("exactly")
+        );
+        assert_eq!(
+            "invalid number for `at-most`: invalid digit found in string",
+            error("at-most:x")
+        );
+        assert_eq!("expected a single letter, not `xy`", error("forbidden:xy"));
+        assert_eq!("`corporate` doesn't take an argument", error("corporate:1"));
+        assert!(error("regex:(").starts_with("regex parse error"));
+        assert!(error("strict").starts_with("unknown policy `strict`, expected one of"));
+    }
+}
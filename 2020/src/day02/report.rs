@@ -0,0 +1,108 @@
+use super::{PasswordEntry, PolicyRule, Verdict};
+use std::fmt::{self, Write};
+
+/// How one entry fared under one rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportRow<'a> {
+    /// The line of the database the entry is on, starting at 1.
+    pub line: usize,
+    pub entry: &'a PasswordEntry,
+    pub rule: String,
+    pub verdict: Verdict,
+}
+
+impl fmt::Display for ReportRow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} [{}] {}: {}",
+            self.line,
+            self.entry,
+            self.rule,
+            if self.verdict.valid { "pass" } else { "fail" },
+            self.verdict.reason
+        )
+    }
+}
+
+/// Every entry under every rule, in database order.
+pub fn report<'a>(entries: &'a [PasswordEntry], rules: &[&dyn PolicyRule]) -> Vec<ReportRow<'a>> {
+    entries
+        .iter()
+        .enumerate()
+        .flat_map(|(i, entry)| {
+            rules.iter().map(move |rule| ReportRow {
+                line: i + 1,
+                entry,
+                rule: rule.to_string(),
+                verdict: rule.explain(entry),
+            })
+        })
+        .collect()
+}
+
+/// Quotes a field if it would otherwise break the row.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn to_csv(rows: &[ReportRow]) -> String {
+    let mut csv = String::from("line,policy,password,rule,valid,observed,reason\n");
+
+    for row in rows {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            row.line,
+            csv_field(&row.entry.policy.to_string()),
+            csv_field(&row.entry.password),
+            csv_field(&row.rule),
+            row.verdict.valid,
+            csv_field(&row.verdict.observed),
+            csv_field(&row.verdict.reason)
+        )
+        .unwrap();
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day02::{load, AuthenticationSystem, CorporatePolicy};
+    use indoc::indoc;
+
+    #[test]
+    fn it_reports_every_entry_under_every_rule() {
+        let entries = load(indoc! {r#"
+            1-3 a: abcde
+            1-3 b: cdefg
+            2-9 c: cc,"ccccc
+        "#})
+        .unwrap();
+        let rows = report(&entries, &[&CorporatePolicy, &AuthenticationSystem]);
+
+        assert_eq!(6, rows.len());
+        assert_eq!(
+            "2: 1-3 b: cdefg [authentication] fail: neither position 1 nor 3 is `b`",
+            rows[3].to_string()
+        );
+        assert_eq!(
+            indoc! {r#"
+                line,policy,password,rule,valid,observed,reason
+                1,1-3 a,abcde,corporate,true,a: 1,1 `a` is within 1-3
+                1,1-3 a,abcde,authentication,true,"1: a, 3: c",only position 1 is `a`
+                2,1-3 b,cdefg,corporate,false,b: 0,0 `b` is fewer than 1
+                2,1-3 b,cdefg,authentication,false,"1: c, 3: e",neither position 1 nor 3 is `b`
+                3,2-9 c,"cc,""ccccc",corporate,true,c: 7,7 `c` is within 2-9
+                3,2-9 c,"cc,""ccccc",authentication,false,"2: c, 9: c",both positions 2 and 9 are `c`
+            "#},
+            to_csv(&rows)
+        );
+    }
+}
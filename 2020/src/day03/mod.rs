@@ -1,8 +1,19 @@
+mod render;
+mod slope;
+
+pub use slope::{fewest_trees, Slope, Stepping};
+
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    grid::Grid,
+    runner::{self, Outcome},
+};
 use anyhow::{bail, Result};
-use std::{convert::TryFrom, str::FromStr};
+use std::convert::TryFrom;
 
-#[derive(Debug, PartialEq)]
-enum Tile {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tile {
     Empty,
     Tree,
 }
@@ -19,56 +30,46 @@ impl TryFrom<char> for Tile {
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Grid(Vec<Vec<Tile>>);
-
-impl FromStr for Grid {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        Ok(Grid(
-            s.lines()
-                .map(|line| line.chars().map(Tile::try_from).collect())
-                .collect::<Result<_, _>>()?,
-        ))
-    }
-}
-
-impl Grid {
-    fn width(&self) -> usize {
-        if !self.0.is_empty() {
-            self.0[0].len()
-        } else {
-            0
+impl From<Tile> for char {
+    fn from(tile: Tile) -> Self {
+        match tile {
+            Tile::Empty => '.',
+            Tile::Tree => '#',
         }
     }
+}
 
-    fn count_trees_for_trajectory(&self, right: usize, down: usize) -> usize {
-        self.0
-            .iter()
-            .step_by(down)
-            .enumerate()
-            .filter(|(i, row)| row[(i * right) % self.width()] == Tile::Tree)
-            .count()
-    }
+/// Parses the map of open squares and trees.
+pub fn load(input: &str) -> Result<Grid<Tile>, ParseError> {
+    Grid::parse(3, input)
 }
 
 #[aoc_generator(day3)]
-fn generator(input: &str) -> Result<Grid> {
-    Grid::from_str(&input)
+fn generator(input: &str) -> Result<Grid<Tile>, ParseError> {
+    load(input)
+}
+
+fn count_trees(input: &Grid<Tile>, right: usize, down: usize) -> usize {
+    let slope = Slope::new(right, down).expect("the puzzle's slopes all go down");
+    input.count_trees(slope, Stepping::Jump)
 }
 
 #[aoc(day3, part1)]
-fn part1(input: &Grid) -> usize {
-    input.count_trees_for_trajectory(3, 1)
+fn part1(input: &Grid<Tile>) -> Answer {
+    count_trees(input, 3, 1).into()
 }
 
 #[aoc(day3, part2)]
-fn part2(input: &Grid) -> usize {
+fn part2(input: &Grid<Tile>) -> Answer {
     [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
         .iter()
-        .map(|&(right, down)| input.count_trees_for_trajectory(right, down))
-        .product()
+        .map(|&(right, down)| count_trees(input, right, down))
+        .product::<usize>()
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(3, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -76,25 +77,32 @@ mod tests {
     use super::*;
     use indoc::indoc;
 
+    /// The example map from the puzzle.
+    pub(super) const EXAMPLE: &str = indoc! {"
+        ..##.......
+        #...#...#..
+        .#....#..#.
+        ..#.#...#.#
+        .#...##..#.
+        ..#.##.....
+        .#.#.#....#
+        .#........#
+        #.##...#...
+        #...##....#
+        .#..#...#.#
+    "};
+
+    pub(super) fn example() -> Grid<Tile> {
+        load(EXAMPLE).unwrap()
+    }
+
     #[test]
     fn it_parses_a_grid() {
-        let data = indoc! {"
-            ..##.......
-            #...#...#..
-            .#....#..#.
-            ..#.#...#.#
-            .#...##..#.
-            ..#.##.....
-            .#.#.#....#
-            .#........#
-            #.##...#...
-            #...##....#
-            .#..#...#.#
-        "};
-        let grid = Grid::from_str(data).unwrap();
+        let data = EXAMPLE;
+        let grid = generator(data).unwrap();
 
         assert_eq!(
-            Grid(vec![
+            vec![
                 vec![
                     Tile::Empty,
                     Tile::Empty,
@@ -238,32 +246,48 @@ mod tests {
                     Tile::Empty,
                     Tile::Tree
                 ]
-            ]),
-            grid
-        )
+            ],
+            grid.rows().map(<[Tile]>::to_vec).collect::<Vec<_>>()
+        );
+        assert_eq!(data, grid.to_string())
     }
 
     #[test]
     fn it_counts_trees_for_a_trajectory() {
+        let grid = example();
+
+        assert_eq!(2, count_trees(&grid, 1, 1));
+        assert_eq!(7, count_trees(&grid, 3, 1));
+        assert_eq!(3, count_trees(&grid, 5, 1));
+        assert_eq!(4, count_trees(&grid, 7, 1));
+        assert_eq!(2, count_trees(&grid, 1, 2))
+    }
+
+    #[test]
+    fn it_reports_invalid_grids() {
+        let data = indoc! {"
+            ..##.......
+            #...#..x#..
+        "};
+
+        assert_eq!(
+            ParseError::new(3, 2, 8, "x", "invalid character: x"),
+            generator(data).unwrap_err()
+        );
+
         let data = indoc! {"
             ..##.......
-            #...#...#..
-            .#....#..#.
-            ..#.#...#.#
-            .#...##..#.
-            ..#.##.....
-            .#.#.#....#
-            .#........#
-            #.##...#...
-            #...##....#
-            .#..#...#.#
+            #...#...#.
         "};
-        let grid = Grid::from_str(data).unwrap();
 
-        assert_eq!(2, grid.count_trees_for_trajectory(1, 1));
-        assert_eq!(7, grid.count_trees_for_trajectory(3, 1));
-        assert_eq!(3, grid.count_trees_for_trajectory(5, 1));
-        assert_eq!(4, grid.count_trees_for_trajectory(7, 1));
-        assert_eq!(2, grid.count_trees_for_trajectory(1, 2))
+        assert_eq!(
+            ParseError::new(3, 2, 1, "#...#...#.", "expected a row of width 11"),
+            generator(data).unwrap_err()
+        );
+
+        assert_eq!(
+            ParseError::new(3, 1, 1, "", "grid is empty"),
+            generator("").unwrap_err()
+        );
     }
 }
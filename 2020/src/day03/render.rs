@@ -0,0 +1,194 @@
+use super::{Slope, Stepping, Tile};
+use crate::{error::SolveError, grid::Grid};
+use std::fmt::Write;
+
+/// The most copies of the map a picture repeats it across, so a steep slope
+/// can't ask for more squares than fit in memory.
+const MAX_COPIES: u128 = 1000;
+
+/// What to draw in a square of the repeated map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Square {
+    Open,
+    Tree,
+    /// An open square the toboggan touched.
+    Passed,
+    /// A tree the toboggan hit.
+    Hit,
+}
+
+impl From<Square> for char {
+    fn from(square: Square) -> Self {
+        match square {
+            Square::Open => '.',
+            Square::Tree => '#',
+            Square::Passed => 'O',
+            Square::Hit => 'X',
+        }
+    }
+}
+
+impl Grid<Tile> {
+    /// The map repeated to the right as far as the path goes, with the path
+    /// drawn over it. Like the puzzle, the starting square isn't marked.
+    fn overlay(&self, slope: Slope, stepping: Stepping) -> Result<Grid<Square>, SolveError> {
+        let path: Vec<_> = self.steps(slope, stepping).collect();
+        let reach = path.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+        let copies = reach.div_ceil(self.width().max(1) as u128).max(1);
+        if copies > MAX_COPIES {
+            return Err(SolveError::new(
+                3,
+                format!(
+                    "slope {} needs {} copies of the map to draw, more than {}",
+                    slope, copies, MAX_COPIES
+                ),
+            ));
+        }
+        let copies = copies as usize;
+
+        let mut overlay = Grid::new(
+            self.width() * copies,
+            self.height(),
+            (0..self.height())
+                .flat_map(|y| (0..self.width() * copies).map(move |x| (x, y)))
+                .map(|(x, y)| match self.get_wrapping(x as isize, y as isize) {
+                    Some(Tile::Tree) => Square::Tree,
+                    _ => Square::Open,
+                })
+                .collect(),
+        );
+
+        for &(x, y) in path.iter().skip(1) {
+            if let Some(square) = overlay.get_mut(x as isize, y as isize) {
+                *square = match square {
+                    Square::Tree | Square::Hit => Square::Hit,
+                    Square::Open | Square::Passed => Square::Passed,
+                };
+            }
+        }
+
+        Ok(overlay)
+    }
+
+    /// Draws the path down the map the way the puzzle does, with `O` for the
+    /// open squares it passes and `X` for the trees it hits.
+    pub fn render(&self, slope: Slope, stepping: Stepping) -> Result<String, SolveError> {
+        Ok(self.overlay(slope, stepping)?.to_string())
+    }
+
+    /// Draws the same picture as [`Grid::render`] as an SVG image.
+    pub fn render_svg(&self, slope: Slope, stepping: Stepping) -> Result<String, SolveError> {
+        const SIZE: usize = 10;
+
+        let overlay = self.overlay(slope, stepping)?;
+        let (width, height) = (overlay.width() * SIZE, overlay.height() * SIZE);
+        let mut svg = String::new();
+
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            width, height, width, height
+        )
+        .unwrap();
+        svg.push_str(
+            "  <style>.tree { fill: #2e7d32; } .passed { fill: #90caf9; } .hit { fill: #c62828; }</style>\n",
+        );
+        writeln!(
+            svg,
+            "  <rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>",
+            width, height
+        )
+        .unwrap();
+
+        for ((x, y), square) in overlay.iter() {
+            let class = match square {
+                Square::Open => continue,
+                Square::Tree => "tree",
+                Square::Passed => "passed",
+                Square::Hit => "hit",
+            };
+
+            writeln!(
+                svg,
+                "  <rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                class,
+                x * SIZE,
+                y * SIZE,
+                SIZE,
+                SIZE
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day03::tests::example;
+    use indoc::indoc;
+
+    #[test]
+    fn it_renders_the_path_like_the_puzzle() {
+        let slope = Slope::new(3, 1).unwrap();
+
+        assert_eq!(
+            indoc! {"
+                ..##.........##.........##.......
+                #..O#...#..#...#...#..#...#...#..
+                .#....X..#..#....#..#..#....#..#.
+                ..#.#...#O#..#.#...#.#..#.#...#.#
+                .#...##..#..X...##..#..#...##..#.
+                ..#.##.......#.X#.......#.##.....
+                .#.#.#....#.#.#.#.O..#.#.#.#....#
+                .#........#.#........X.#........#
+                #.##...#...#.##...#...#.X#...#...
+                #...##....##...##....##...#X....#
+                .#..#...#.#.#..#...#.#.#..#...X.#
+            "},
+            example().render(slope, Stepping::Jump).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_refuses_to_draw_too_many_copies() {
+        let grid = example();
+        let slope = Slope::new(usize::MAX, 1).unwrap();
+
+        assert_eq!(
+            Err(SolveError::new(
+                3,
+                "slope 18446744073709551615/1 needs 16769767339735956014 copies of the map \
+                 to draw, more than 1000"
+            )),
+            grid.render(slope, Stepping::Jump)
+        );
+        assert!(grid
+            .render_svg(Slope::new(1_000_000_000, 1).unwrap(), Stepping::Line)
+            .is_err());
+        assert!(grid
+            .render(Slope::new(1099, 1).unwrap(), Stepping::Jump)
+            .is_ok());
+    }
+
+    #[test]
+    fn it_renders_svg() {
+        let svg = example()
+            .render_svg(Slope::new(1, 2).unwrap(), Stepping::Jump)
+            .unwrap();
+
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"110\" height=\"110\" viewBox=\"0 0 110 110\">\n"
+        ));
+        assert!(svg
+            .contains("  <rect class=\"passed\" x=\"20\" y=\"40\" width=\"10\" height=\"10\"/>\n"));
+        assert!(
+            svg.contains("  <rect class=\"hit\" x=\"30\" y=\"60\" width=\"10\" height=\"10\"/>\n")
+        );
+        assert_eq!(2, svg.matches("class=\"hit\"").count());
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,275 @@
+use super::Tile;
+use crate::grid::{Grid, Position};
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+/// A step of `right` squares across and `down` squares down, which needn't
+/// divide evenly, like `3/2`.
+///
+/// This is a step rather than a ratio: `2/2` jumps over every other row where
+/// `1/1` doesn't, so the two are different slopes. They only follow the
+/// same line, which [`Slope::reduced`] finds.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Slope {
+    right: usize,
+    down: usize,
+}
+
+impl Slope {
+    /// A slope, unless `down` is 0 and it would never reach the bottom.
+    pub fn new(right: usize, down: usize) -> Option<Self> {
+        if down == 0 {
+            None
+        } else {
+            Some(Slope { right, down })
+        }
+    }
+
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    pub fn down(&self) -> usize {
+        self.down
+    }
+
+    /// The smallest step in the same direction, like `1/1` for `2/2`.
+    pub fn reduced(&self) -> Slope {
+        let (mut a, mut b) = (self.right, self.down);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+
+        Slope {
+            right: self.right / a,
+            down: self.down / a,
+        }
+    }
+
+    /// Every slope with `right` and `down` in the ranges, right changing
+    /// fastest.
+    pub fn range(
+        right: RangeInclusive<usize>,
+        down: RangeInclusive<usize>,
+    ) -> impl Iterator<Item = Slope> {
+        down.filter(|&down| down > 0)
+            .flat_map(move |down| right.clone().map(move |right| Slope { right, down }))
+    }
+}
+
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.right, self.down)
+    }
+}
+
+impl FromStr for Slope {
+    type Err = String;
+
+    /// Parses `right/down`, or just `right` to go down one at a time.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (right, down) = s.split_once('/').unwrap_or((s, "1"));
+        let number = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|e| format!("invalid slope `{}`: {}", s, e))
+        };
+
+        Slope::new(number(right)?, number(down)?)
+            .ok_or_else(|| format!("invalid slope `{}`: down must be at least 1", s))
+    }
+}
+
+/// How the toboggan follows a slope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stepping {
+    /// Move `right` and `down` at a time, only touching the squares it lands
+    /// on, as in the puzzle.
+    Jump,
+    /// Pass through every row at the column nearest the line, rounding
+    /// halves up, like Bresenham's line algorithm. Only the direction of the
+    /// slope matters.
+    Line,
+}
+
+impl Grid<Tile> {
+    /// The squares the toboggan touches from the top left to the bottom, with
+    /// columns counted as if the map repeated to the right forever.
+    ///
+    /// Columns are worked out in a u128, where a row number times a step
+    /// can't overflow.
+    pub(super) fn steps(
+        &self,
+        slope: Slope,
+        stepping: Stepping,
+    ) -> impl Iterator<Item = (u128, usize)> {
+        let Slope { right, down } = slope;
+        let (right, down) = (right as u128, down as u128);
+        let step = match stepping {
+            Stepping::Jump => slope.down,
+            Stepping::Line => 1,
+        };
+
+        (0..self.height())
+            .step_by(step)
+            .enumerate()
+            .map(move |(i, y)| match stepping {
+                Stepping::Jump => (i as u128 * right, y),
+                Stepping::Line => {
+                    // The line is at `y * right / down` across.
+                    let across = y as u128 * right;
+                    let rest = across % down;
+                    (across / down + u128::from(rest >= down - rest), y)
+                }
+            })
+    }
+
+    /// The squares the toboggan touches from the top left to the bottom, with
+    /// columns wrapped to the width of the map.
+    pub fn path(&self, slope: Slope, stepping: Stepping) -> Vec<Position> {
+        let width = self.width().max(1) as u128;
+        self.steps(slope, stepping)
+            .map(|(x, y)| ((x % width) as usize, y))
+            .collect()
+    }
+
+    pub fn count_trees(&self, slope: Slope, stepping: Stepping) -> usize {
+        self.path(slope, stepping)
+            .into_iter()
+            .filter(|&(x, y)| self.get(x as isize, y as isize) == Some(&Tile::Tree))
+            .count()
+    }
+
+    /// How many trees the toboggan hits on each of the slopes, in order.
+    pub fn sweep(
+        &self,
+        slopes: impl IntoIterator<Item = Slope>,
+        stepping: Stepping,
+    ) -> Vec<(Slope, usize)> {
+        slopes
+            .into_iter()
+            .map(|slope| (slope, self.count_trees(slope, stepping)))
+            .collect()
+    }
+}
+
+/// The slope that hits the fewest trees, the earliest on a tie.
+pub fn fewest_trees(table: &[(Slope, usize)]) -> Option<(Slope, usize)> {
+    table
+        .iter()
+        .copied()
+        .fold(None, |best, (slope, trees)| match best {
+            Some((_, fewest)) if fewest <= trees => best,
+            _ => Some((slope, trees)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day03::tests::example;
+
+    #[test]
+    fn it_steps_along_fractional_slopes() {
+        let grid = example();
+        let slope = "1/2".parse().unwrap();
+
+        assert_eq!(
+            vec![(0, 0), (1, 2), (2, 4), (3, 6), (4, 8), (5, 10)],
+            grid.path(slope, Stepping::Jump)
+        );
+        assert_eq!(
+            vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5],
+            grid.path(slope, Stepping::Line)
+                .into_iter()
+                .map(|(x, _)| x)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0, 2, 3, 5, 6, 8, 9, 0, 1, 3, 4],
+            grid.path("3/2".parse().unwrap(), Stepping::Line)
+                .into_iter()
+                .map(|(x, _)| x)
+                .collect::<Vec<_>>()
+        );
+
+        for right in 0..8 {
+            let slope = Slope::new(right, 1).unwrap();
+            assert_eq!(
+                grid.path(slope, Stepping::Jump),
+                grid.path(slope, Stepping::Line)
+            );
+        }
+    }
+
+    #[test]
+    fn it_counts_trees_on_huge_slopes() {
+        let grid = example();
+        let count = |slope: &str, stepping| grid.count_trees(slope.parse().unwrap(), stepping);
+
+        // 2^64 - 2 is 3 more than a multiple of 11, the width of the map.
+        assert_eq!(7, count("18446744073709551614/1", Stepping::Jump));
+        assert_eq!(1, count("18446744073709551613/1", Stepping::Jump));
+        assert_eq!(3, count("18446744073709551611/1", Stepping::Jump));
+        assert_eq!(2, count("18446744073709551615/1", Stepping::Jump));
+        assert_eq!(
+            count("3/1", Stepping::Jump),
+            count("18446744073709551614/1", Stepping::Line)
+        );
+
+        assert_eq!(0, count("18446744073709551614/3", Stepping::Jump));
+        assert_eq!(1, count("18446744073709551614/3", Stepping::Line));
+        assert_eq!(6, count("18446744073709551613/3", Stepping::Line));
+        assert_eq!(3, count("18446744073709551615/3", Stepping::Line));
+    }
+
+    #[test]
+    fn it_reduces_slopes() {
+        assert_eq!(Slope::new(1, 1), Some(Slope::new(2, 2).unwrap().reduced()));
+        assert_eq!(Slope::new(3, 2), Some(Slope::new(6, 4).unwrap().reduced()));
+        assert_eq!(Slope::new(0, 1), Some(Slope::new(0, 5).unwrap().reduced()));
+
+        let grid = example();
+        assert_eq!(
+            grid.path(Slope::new(1, 2).unwrap(), Stepping::Line),
+            grid.path(Slope::new(2, 4).unwrap(), Stepping::Line)
+        );
+        assert_ne!(
+            grid.path(Slope::new(1, 1).unwrap(), Stepping::Jump),
+            grid.path(Slope::new(2, 2).unwrap(), Stepping::Jump)
+        );
+    }
+
+    #[test]
+    fn it_sweeps_slopes() {
+        let grid = example();
+        let table = grid.sweep(Slope::range(1..=3, 1..=2), Stepping::Jump);
+
+        assert_eq!(
+            vec![
+                (String::from("1/1"), 2),
+                (String::from("2/1"), 1),
+                (String::from("3/1"), 7),
+                (String::from("1/2"), 2),
+                (String::from("2/2"), 1),
+                (String::from("3/2"), 2)
+            ],
+            table
+                .iter()
+                .map(|(slope, trees)| (slope.to_string(), *trees))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some((Slope::new(2, 1).unwrap(), 1)), fewest_trees(&table));
+        assert_eq!(None, fewest_trees(&[]));
+    }
+
+    #[test]
+    fn it_parses_slopes() {
+        assert_eq!(Ok(Slope { right: 3, down: 1 }), "3".parse());
+        assert_eq!(Ok(Slope { right: 1, down: 2 }), "1/2".parse());
+        assert_eq!(
+            Err(String::from("invalid slope `1/0`: down must be at least 1")),
+            "1/0".parse::<Slope>()
+        );
+        assert!("x/1".parse::<Slope>().is_err());
+    }
+}
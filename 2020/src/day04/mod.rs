@@ -1,22 +1,27 @@
-use anyhow::Result;
-use itertools::Itertools;
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    input,
+    runner::{self, Outcome},
+};
 use std::{collections::HashMap, str::FromStr};
 
 #[derive(Debug, PartialEq)]
 struct Passport(HashMap<String, String>);
 
 impl FromStr for Passport {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let passport: HashMap<_, _> = s
-            .split_whitespace()
-            .flat_map(|p| p.split(':'))
-            .tuples()
-            .map(|(k, v)| (String::from(k), String::from(v)))
-            .collect();
-
-        Ok(Passport(passport))
+        s.split_whitespace()
+            .map(|field| {
+                field
+                    .split_once(':')
+                    .map(|(k, v)| (String::from(k), String::from(v)))
+                    .ok_or_else(|| ParseError::at(4, s, field, "expected a field like `key:value`"))
+            })
+            .collect::<Result<_, _>>()
+            .map(Passport)
     }
 }
 
@@ -45,21 +50,21 @@ impl Passport {
                 .parse::<i32>()
                 .map(|v| (2020..=2030).contains(&v))
                 .unwrap_or_default(),
-            "hgt" => match v.split_at(v.len() - 2) {
-                (v, "cm") => v
+            "hgt" => match (v.strip_suffix("cm"), v.strip_suffix("in")) {
+                (Some(v), _) => v
                     .parse::<i32>()
                     .map(|v| (150..=193).contains(&v))
                     .unwrap_or_default(),
-                (v, "in") => v
+                (_, Some(v)) => v
                     .parse::<i32>()
                     .map(|v| (59..=76).contains(&v))
                     .unwrap_or_default(),
                 _ => false,
             },
             "hcl" => {
-                v.starts_with("#")
+                v.starts_with('#')
                     && v.len() == 7
-                    && v.chars().skip(1).all(|c| c.to_digit(16).is_some())
+                    && v.chars().skip(1).all(|c| c.is_ascii_hexdigit())
             }
             "ecl" => ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&v.as_str()),
             "pid" => v.len() == 9 && v.chars().all(|c| c.is_numeric()),
@@ -69,21 +74,32 @@ impl Passport {
 }
 
 #[aoc_generator(day4)]
-fn generator(input: &str) -> Result<Vec<Passport>> {
-    input.split("\n\n").map(Passport::from_str).collect()
+fn generator(data: &str) -> Result<Vec<Passport>, ParseError> {
+    input::paragraphs(data)
+        .map(|(offset, paragraph)| Passport::from_str(paragraph).map_err(|e| e.offset(offset)))
+        .collect()
 }
 
 #[aoc(day4, part1)]
-fn part1(input: &[Passport]) -> usize {
+fn part1(input: &[Passport]) -> Answer {
     input
         .iter()
         .filter(|passport| passport.has_required_fields())
         .count()
+        .into()
 }
 
 #[aoc(day4, part2)]
-fn part2(input: &[Passport]) -> usize {
-    input.iter().filter(|passport| passport.is_valid()).count()
+fn part2(input: &[Passport]) -> Answer {
+    input
+        .iter()
+        .filter(|passport| passport.is_valid())
+        .count()
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(4, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -189,4 +205,28 @@ mod tests {
             input.iter().filter(|passport| passport.is_valid()).count()
         )
     }
+
+    #[test]
+    fn it_reports_invalid_fields() {
+        let data = indoc! {"
+            ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+            byr:1937 iyr:2017 cid:147 hgt:183cm
+
+            iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+            hcl:#cfa07d byr1929
+        "};
+
+        assert_eq!(
+            ParseError::new(4, 5, 13, "byr1929", "expected a field like `key:value`"),
+            generator(data).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn it_rejects_short_heights() {
+        let data = "pid:087499704 hgt:7 ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f";
+        let passport = Passport::from_str(data).unwrap();
+
+        assert!(!passport.is_valid());
+    }
 }
@@ -1,4 +1,8 @@
-use anyhow::{bail, Result};
+use crate::{
+    answer::Answer,
+    error::{ParseError, SolveError},
+    runner::{self, Outcome},
+};
 use itertools::Itertools;
 use std::str::FromStr;
 
@@ -9,27 +13,35 @@ struct Seat {
 }
 
 impl FromStr for Seat {
-    type Err = anyhow::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts = s.split_at(7);
+        let split = match s.char_indices().nth(7) {
+            Some((split, _)) if s.chars().count() == 10 => split,
+            _ => return Err(ParseError::at(5, s, s, "expected 10 characters")),
+        };
+        let parts = s.split_at(split);
 
-        let row = parts.0.chars().try_fold(0u8, |result, c| match c {
-            'F' => Ok(result.wrapping_shl(1) | 0),
-            'B' => Ok(result.wrapping_shl(1) | 1),
-            _ => bail!("invalid character: {}", c),
-        })?;
-
-        let column = parts.1.chars().try_fold(0u8, |result, c| match c {
-            'L' => Ok(result.wrapping_shl(1) | 0),
-            'R' => Ok(result.wrapping_shl(1) | 1),
-            _ => bail!("invalid character in column: {}", c),
-        })?;
+        let row = decode(s, parts.0, 'F', 'B')?;
+        let column = decode(s, parts.1, 'L', 'R')?;
 
         Ok(Seat { row, column })
     }
 }
 
+fn decode(source: &str, part: &str, zero: char, one: char) -> Result<u8, ParseError> {
+    part.char_indices().try_fold(0u8, |result, (i, c)| match c {
+        c if c == zero => Ok(result.wrapping_shl(1)),
+        c if c == one => Ok(result.wrapping_shl(1) | 1),
+        c => Err(ParseError::at(
+            5,
+            source,
+            &part[i..i + c.len_utf8()],
+            format!("expected `{}` or `{}`", zero, one),
+        )),
+    })
+}
+
 impl Seat {
     fn id(&self) -> u16 {
         u16::from(self.row) * 8 + u16::from(self.column)
@@ -37,27 +49,39 @@ impl Seat {
 }
 
 #[aoc_generator(day5)]
-fn generator(data: &str) -> Result<Vec<Seat>> {
+fn generator(data: &str) -> Result<Vec<Seat>, ParseError> {
     data.lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .map(Seat::from_str)
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| Seat::from_str(line).map_err(|e| e.offset(i)))
         .collect()
 }
 
 #[aoc(day5, part1)]
-fn part1(input: &[Seat]) -> Option<u16> {
-    input.iter().map(|s| s.id()).max()
+fn part1(input: &[Seat]) -> Answer {
+    input
+        .iter()
+        .map(|s| s.id())
+        .max()
+        .ok_or_else(|| SolveError::new(5, "no boarding passes"))
+        .into()
 }
 
 #[aoc(day5, part2)]
-fn part2(input: &[Seat]) -> Option<u16> {
+fn part2(input: &[Seat]) -> Answer {
     input
         .iter()
         .map(|s| s.id())
         .sorted()
         .tuple_windows::<(_, _)>()
         .find_map(|(s1, s2)| if s1 + 2 == s2 { Some(s1 + 1) } else { None })
+        .ok_or_else(|| SolveError::new(5, "no free seat between two taken seats"))
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(5, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -81,4 +105,19 @@ mod tests {
 
         assert_eq!((102, 4, 820), (seat.row, seat.column, seat.id()))
     }
+
+    #[test]
+    fn it_reports_invalid_seats() {
+        let data = "BFFFBBFRRR\nFFFBBXFRRR\nBBFF";
+
+        assert_eq!(
+            ParseError::new(5, 2, 6, "X", "expected `F` or `B`"),
+            generator(data).unwrap_err()
+        );
+
+        assert_eq!(
+            ParseError::new(5, 1, 1, "BBFF", "expected 10 characters"),
+            Seat::from_str("BBFF").unwrap_err()
+        );
+    }
 }
@@ -1,22 +1,44 @@
-use anyhow::Result;
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    input,
+    runner::{self, Outcome},
+};
 use itertools::Itertools;
 use std::collections::HashSet;
 
 #[aoc_generator(day6)]
-fn generator(input: &str) -> Vec<String> {
-    input.split("\n\n").map(String::from).collect()
+fn generator(data: &str) -> Result<Vec<String>, ParseError> {
+    input::paragraphs(data)
+        .map(|(offset, group)| {
+            match group
+                .char_indices()
+                .find(|(_, c)| !c.is_ascii_lowercase() && !c.is_whitespace())
+            {
+                Some((i, c)) => Err(ParseError::at(
+                    6,
+                    group,
+                    &group[i..i + c.len_utf8()],
+                    "expected a question from `a` to `z`",
+                )
+                .offset(offset)),
+                None => Ok(String::from(group)),
+            }
+        })
+        .collect()
 }
 
 #[aoc(day6, part1)]
-fn part1(input: &[String]) -> usize {
+fn part1(input: &[String]) -> Answer {
     input
         .iter()
         .map(|s| s.chars().filter(|c| !c.is_whitespace()).unique().count())
         .sum::<usize>()
+        .into()
 }
 
 #[aoc(day6, part2)]
-fn part2(input: &[String]) -> usize {
+fn part2(input: &[String]) -> Answer {
     input
         .iter()
         .map(|s| {
@@ -26,6 +48,11 @@ fn part2(input: &[String]) -> usize {
         })
         .map(|s| s.unwrap_or_default().len())
         .sum::<usize>()
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(6, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -52,7 +79,7 @@ mod tests {
 
             b
         "};
-        let input = generator(data);
+        let input = generator(data).unwrap();
         assert_eq!(5, input.len())
     }
 
@@ -75,8 +102,8 @@ mod tests {
 
             b
         "};
-        let input = generator(data);
-        assert_eq!(11, part1(&input))
+        let input = generator(data).unwrap();
+        assert_eq!(Answer::Integer(11), part1(&input))
     }
 
     #[test]
@@ -98,7 +125,22 @@ mod tests {
 
             b
         "};
-        let input = generator(data);
-        assert_eq!(6, part2(&input))
+        let input = generator(data).unwrap();
+        assert_eq!(Answer::Integer(6), part2(&input))
+    }
+
+    #[test]
+    fn it_reports_invalid_answers() {
+        let data = indoc! {"
+            abc
+
+            a
+            bC
+        "};
+
+        assert_eq!(
+            ParseError::new(6, 4, 2, "C", "expected a question from `a` to `z`"),
+            generator(data).unwrap_err()
+        )
     }
 }
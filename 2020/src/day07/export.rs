@@ -0,0 +1,188 @@
+use super::{BagGraph, Colour};
+use std::fmt::Write;
+
+/// Which part of the rules to draw.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Subgraph {
+    All,
+    /// A colour and everything it eventually holds.
+    From(Colour),
+    /// A colour and everything that eventually holds it.
+    To(Colour),
+}
+
+impl BagGraph {
+    fn select(&self, subgraph: Subgraph) -> (Vec<bool>, Option<Colour>) {
+        let (focus, colours) = match subgraph {
+            Subgraph::All => return (vec![true; self.len()], None),
+            Subgraph::From(colour) => (colour, self.contained_in(colour)),
+            Subgraph::To(colour) => (colour, self.containing(colour)),
+        };
+
+        let mut selected = vec![false; self.len()];
+        selected[focus] = true;
+        for colour in colours {
+            selected[colour] = true;
+        }
+
+        (selected, Some(focus))
+    }
+
+    /// The selected edges as `(container, number, inner)`.
+    fn edges<'a>(
+        &'a self,
+        selected: &'a [bool],
+    ) -> impl Iterator<Item = (Colour, usize, Colour)> + 'a {
+        (0..self.len())
+            .filter(move |&colour| selected[colour])
+            .flat_map(move |colour| {
+                self.contents(colour)
+                    .iter()
+                    .filter(move |&&(_, inner)| selected[inner])
+                    .map(move |&(number, inner)| (colour, number, inner))
+            })
+    }
+
+    /// Renders the rules as a Graphviz digraph with an edge from each bag to
+    /// the bags it holds, labelled with how many.
+    ///
+    /// The focus colour of a subgraph is filled in, and colours without a
+    /// rule of their own are dashed.
+    pub fn to_dot(&self, subgraph: Subgraph) -> String {
+        let (selected, focus) = self.select(subgraph);
+        let mut dot = String::from("digraph bags {\n    node [shape=box];\n");
+
+        for colour in (0..self.len()).filter(|&colour| selected[colour]) {
+            let style = if Some(colour) == focus {
+                ", style=filled, fillcolor=gold"
+            } else if !self.is_defined(colour) {
+                ", style=dashed"
+            } else {
+                ""
+            };
+
+            writeln!(
+                dot,
+                "    c{} [label=\"{}\"{}];",
+                colour,
+                self.name(colour),
+                style
+            )
+            .unwrap();
+        }
+
+        for (colour, number, inner) in self.edges(&selected) {
+            writeln!(dot, "    c{} -> c{} [label=\"{}\"];", colour, inner, number).unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the rules as a Mermaid flowchart, styled like
+    /// [`BagGraph::to_dot`].
+    pub fn to_mermaid(&self, subgraph: Subgraph) -> String {
+        let (selected, focus) = self.select(subgraph);
+        let mut mermaid = String::from("flowchart LR\n");
+
+        for colour in (0..self.len()).filter(|&colour| selected[colour]) {
+            writeln!(mermaid, "    c{}[\"{}\"]", colour, self.name(colour)).unwrap();
+        }
+
+        for (colour, number, inner) in self.edges(&selected) {
+            writeln!(mermaid, "    c{} -->|{}| c{}", colour, number, inner).unwrap();
+        }
+
+        if let Some(focus) = focus {
+            writeln!(mermaid, "    style c{} fill:gold", focus).unwrap();
+        }
+
+        for colour in (0..self.len()).filter(|&colour| selected[colour] && !self.is_defined(colour))
+        {
+            writeln!(mermaid, "    style c{} stroke-dasharray:5 5", colour).unwrap();
+        }
+
+        mermaid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day07::load;
+    use indoc::indoc;
+
+    fn graph() -> BagGraph {
+        load(indoc! {"
+            light red bags contain 1 bright white bag, 2 muted yellow bags.
+            bright white bags contain 1 shiny gold bag.
+            muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+            shiny gold bags contain 1 dark olive bag.
+            dark olive bags contain 3 faded blue bags.
+        "})
+        .unwrap()
+    }
+
+    #[test]
+    fn it_exports_dot() {
+        let graph = graph();
+
+        assert_eq!(
+            indoc! {r#"
+                digraph bags {
+                    node [shape=box];
+                    c0 [label="light red"];
+                    c1 [label="bright white"];
+                    c2 [label="muted yellow"];
+                    c3 [label="shiny gold"];
+                    c4 [label="faded blue", style=dashed];
+                    c5 [label="dark olive"];
+                    c0 -> c1 [label="1"];
+                    c0 -> c2 [label="2"];
+                    c1 -> c3 [label="1"];
+                    c2 -> c3 [label="2"];
+                    c2 -> c4 [label="9"];
+                    c3 -> c5 [label="1"];
+                    c5 -> c4 [label="3"];
+                }
+            "#},
+            graph.to_dot(Subgraph::All)
+        );
+    }
+
+    #[test]
+    fn it_exports_subgraphs() {
+        let graph = graph();
+        let gold = graph.id("shiny gold").unwrap();
+
+        assert_eq!(
+            indoc! {r#"
+                digraph bags {
+                    node [shape=box];
+                    c3 [label="shiny gold", style=filled, fillcolor=gold];
+                    c4 [label="faded blue", style=dashed];
+                    c5 [label="dark olive"];
+                    c3 -> c5 [label="1"];
+                    c5 -> c4 [label="3"];
+                }
+            "#},
+            graph.to_dot(Subgraph::From(gold))
+        );
+
+        assert_eq!(
+            indoc! {r#"
+                flowchart LR
+                    c0["light red"]
+                    c1["bright white"]
+                    c2["muted yellow"]
+                    c3["shiny gold"]
+                    c0 -->|1| c1
+                    c0 -->|2| c2
+                    c1 -->|1| c3
+                    c2 -->|2| c3
+                    style c3 fill:gold
+            "#},
+            graph.to_mermaid(Subgraph::To(gold))
+        );
+    }
+}
@@ -0,0 +1,556 @@
+use crate::error::SolveError;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+pub type Colour = usize;
+
+/// A problem with the rules that would make counting bags meaningless.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RuleError {
+    /// Colours that each directly hold the next, with the last holding the
+    /// first again. A single colour holds itself.
+    Cycle(Vec<String>),
+    /// A colour found inside `container` bags that has no rule of its own.
+    Undefined { colour: String, container: String },
+    /// A colour with more than one rule, on each of `lines`. The last one is
+    /// used.
+    Duplicate { colour: String, lines: Vec<usize> },
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Cycle(colours) if colours.len() == 1 => {
+                write!(f, "{} bags contain themselves", colours[0])
+            }
+            RuleError::Cycle(colours) => write!(
+                f,
+                "bags contain each other: {} -> {}",
+                colours.join(" -> "),
+                colours[0]
+            ),
+            RuleError::Undefined { colour, container } => write!(
+                f,
+                "no rule for {} bags, found inside {} bags",
+                colour, container
+            ),
+            RuleError::Duplicate { colour, lines } => {
+                let lines: Vec<_> = lines.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "more than one rule for {} bags, on lines {}",
+                    colour,
+                    lines.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// The bag rules as a graph, with every colour interned to a dense id.
+///
+/// Edges run both ways so "what's inside" and "what can hold this" are both
+/// simple lookups, and the number of bags inside each colour is remembered
+/// once worked out. Rules are taken to be one per line, in order, for
+/// reporting where duplicates are.
+#[derive(Debug, Default)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, Colour>,
+    defined: Vec<bool>,
+    /// The lines each colour's rules are on, starting at 1.
+    lines: Vec<Vec<usize>>,
+    contents: Vec<Vec<(usize, Colour)>>,
+    containers: Vec<Vec<Colour>>,
+    counts: RefCell<Vec<Option<usize>>>,
+}
+
+impl BagGraph {
+    pub fn new<I, C>(rules: I) -> Self
+    where
+        I: IntoIterator<Item = (String, C)>,
+        C: IntoIterator<Item = (usize, String)>,
+    {
+        let mut graph = BagGraph::default();
+
+        for (i, (colour, contents)) in rules.into_iter().enumerate() {
+            let colour = graph.intern(colour);
+            let contents: Vec<_> = contents
+                .into_iter()
+                .map(|(number, inner)| (number, graph.intern(inner)))
+                .collect();
+
+            graph.lines[colour].push(i + 1);
+            graph.defined[colour] = true;
+            graph.contents[colour] = contents;
+        }
+
+        for (colour, contents) in graph.contents.iter().enumerate() {
+            for &(_, inner) in contents {
+                graph.containers[inner].push(colour);
+            }
+        }
+
+        graph.counts = RefCell::new(vec![None; graph.names.len()]);
+        graph
+    }
+
+    fn intern(&mut self, name: String) -> Colour {
+        if let Some(&id) = self.ids.get(&name) {
+            return id;
+        }
+
+        let id = self.names.len();
+        self.ids.insert(name.clone(), id);
+        self.names.push(name);
+        self.defined.push(false);
+        self.lines.push(Vec::new());
+        self.contents.push(Vec::new());
+        self.containers.push(Vec::new());
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn id(&self, name: &str) -> Option<Colour> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, colour: Colour) -> &str {
+        &self.names[colour]
+    }
+
+    /// Whether the colour has a rule of its own, rather than only appearing
+    /// inside other bags.
+    pub fn is_defined(&self, colour: Colour) -> bool {
+        self.defined[colour]
+    }
+
+    pub fn contents(&self, colour: Colour) -> &[(usize, Colour)] {
+        &self.contents[colour]
+    }
+
+    /// The colours that directly hold at least one `colour` bag.
+    pub fn containers(&self, colour: Colour) -> &[Colour] {
+        &self.containers[colour]
+    }
+
+    /// Every colour that eventually holds a `colour` bag, found by a single
+    /// walk up the reverse edges.
+    pub fn containing(&self, colour: Colour) -> Vec<Colour> {
+        self.reachable(colour, |colour| self.containers(colour).iter().copied())
+    }
+
+    /// Every colour a `colour` bag eventually holds.
+    pub fn contained_in(&self, colour: Colour) -> Vec<Colour> {
+        self.reachable(colour, |colour| {
+            self.contents(colour).iter().map(|&(_, inner)| inner)
+        })
+    }
+
+    /// Every colour reachable from `colour` by following `next`, breadth
+    /// first, not counting `colour` unless it is reached again.
+    fn reachable<I>(&self, colour: Colour, next: impl Fn(Colour) -> I) -> Vec<Colour>
+    where
+        I: IntoIterator<Item = Colour>,
+    {
+        let mut seen = vec![false; self.len()];
+        let mut queue = VecDeque::from(vec![colour]);
+        let mut found = Vec::new();
+
+        while let Some(colour) = queue.pop_front() {
+            for next in next(colour) {
+                if !seen[next] {
+                    seen[next] = true;
+                    found.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Checks the rules for duplicates, colours without rules and bags that
+    /// eventually contain themselves.
+    pub fn validate(&self) -> Result<(), Vec<RuleError>> {
+        let mut errors: Vec<_> = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, lines)| lines.len() > 1)
+            .map(|(colour, lines)| RuleError::Duplicate {
+                colour: self.name(colour).to_owned(),
+                lines: lines.clone(),
+            })
+            .collect();
+
+        for (container, contents) in self.contents.iter().enumerate() {
+            for &(_, colour) in contents {
+                if !self.defined[colour] {
+                    errors.push(RuleError::Undefined {
+                        colour: self.name(colour).to_owned(),
+                        container: self.name(container).to_owned(),
+                    });
+                }
+            }
+        }
+
+        errors.extend(self.cycles().into_iter().map(|cycle| {
+            RuleError::Cycle(
+                cycle
+                    .into_iter()
+                    .map(|colour| self.name(colour).to_owned())
+                    .collect(),
+            )
+        }));
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// One cycle from each strongly connected component that has any, found
+    /// with Tarjan's algorithm, so every colour that is part of a cycle is in
+    /// the same component as exactly one of them.
+    fn cycles(&self) -> Vec<Vec<Colour>> {
+        const UNVISITED: usize = usize::MAX;
+
+        let mut index = vec![UNVISITED; self.len()];
+        let mut low = vec![0; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        let mut components = Vec::new();
+
+        for root in 0..self.len() {
+            if index[root] != UNVISITED {
+                continue;
+            }
+
+            let mut path = vec![(root, 0)];
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&mut (colour, ref mut next)) = path.last_mut() {
+                match self.contents(colour).get(*next) {
+                    Some(&(_, inner)) => {
+                        *next += 1;
+
+                        if index[inner] == UNVISITED {
+                            index[inner] = next_index;
+                            low[inner] = next_index;
+                            next_index += 1;
+                            stack.push(inner);
+                            on_stack[inner] = true;
+                            path.push((inner, 0));
+                        } else if on_stack[inner] {
+                            low[colour] = low[colour].min(index[inner]);
+                        }
+                    }
+                    None => {
+                        path.pop();
+                        if let Some(&(parent, _)) = path.last() {
+                            low[parent] = low[parent].min(low[colour]);
+                        }
+
+                        if low[colour] != index[colour] {
+                            continue;
+                        }
+
+                        let mut component = Vec::new();
+                        while let Some(member) = stack.pop() {
+                            on_stack[member] = false;
+                            component.push(member);
+                            if member == colour {
+                                break;
+                            }
+                        }
+
+                        let holds_itself = self
+                            .contents(colour)
+                            .iter()
+                            .any(|&(_, inner)| inner == colour);
+                        if component.len() > 1 || holds_itself {
+                            component.sort_unstable();
+                            components.push(component);
+                        }
+                    }
+                }
+            }
+        }
+
+        components.sort_unstable();
+        components
+            .iter()
+            .map(|component| self.shortest_cycle(component))
+            .collect()
+    }
+
+    /// The shortest cycle from a component's first colour back to itself,
+    /// staying inside the component, as the colours along it in order.
+    fn shortest_cycle(&self, component: &[Colour]) -> Vec<Colour> {
+        let start = component[0];
+        let mut parent = vec![None; self.len()];
+        let mut queue = VecDeque::from(vec![start]);
+
+        while let Some(colour) = queue.pop_front() {
+            for &(_, inner) in self.contents(colour) {
+                if inner == start {
+                    let mut cycle = vec![colour];
+                    while let Some(previous) = parent[*cycle.last().unwrap()] {
+                        cycle.push(previous);
+                    }
+                    cycle.reverse();
+                    return cycle;
+                }
+
+                if parent[inner].is_none() && component.binary_search(&inner).is_ok() {
+                    parent[inner] = Some(colour);
+                    queue.push_back(inner);
+                }
+            }
+        }
+
+        unreachable!("a component with a cycle has one through each member")
+    }
+
+    /// How many bags a `colour` bag holds in total, not counting itself, or an
+    /// error if there are too many to count in a `usize`.
+    pub fn count_contents(&self, colour: Colour) -> Result<usize, SolveError> {
+        let mut counts = self.counts.borrow_mut();
+        let mut in_progress = vec![false; self.len()];
+        let mut stack = vec![(colour, false)];
+
+        while let Some((colour, expanded)) = stack.pop() {
+            if counts[colour].is_some() {
+                continue;
+            }
+
+            if !self.defined[colour] {
+                return Err(SolveError::new(
+                    7,
+                    format!("no rule for {} bags", self.name(colour)),
+                ));
+            }
+
+            if expanded {
+                let count = self
+                    .contents(colour)
+                    .iter()
+                    .try_fold(0usize, |total, &(number, inner)| {
+                        counts[inner]
+                            .unwrap_or_default()
+                            .checked_add(1)
+                            .and_then(|each| number.checked_mul(each))
+                            .and_then(|bags| total.checked_add(bags))
+                    })
+                    .ok_or_else(|| {
+                        SolveError::new(
+                            7,
+                            format!("{} bags hold too many bags to count", self.name(colour)),
+                        )
+                    })?;
+
+                counts[colour] = Some(count);
+                in_progress[colour] = false;
+            } else {
+                if in_progress[colour] {
+                    return Err(SolveError::new(
+                        7,
+                        format!("{} bags eventually contain themselves", self.name(colour)),
+                    ));
+                }
+
+                in_progress[colour] = true;
+                stack.push((colour, true));
+                stack.extend(
+                    self.contents(colour)
+                        .iter()
+                        .map(|&(_, inner)| (inner, false)),
+                );
+            }
+        }
+
+        Ok(counts[colour].unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(rules: &[(&str, &[(usize, &str)])]) -> BagGraph {
+        BagGraph::new(rules.iter().map(|(colour, contents)| {
+            (
+                colour.to_string(),
+                contents
+                    .iter()
+                    .map(|&(number, inner)| (number, inner.to_string()))
+                    .collect::<Vec<_>>(),
+            )
+        }))
+    }
+
+    #[test]
+    fn it_indexes_both_directions() {
+        let graph = graph(&[
+            ("red", &[(1, "white"), (2, "yellow")]),
+            ("white", &[(3, "gold")]),
+            ("yellow", &[(4, "gold")]),
+            ("gold", &[]),
+        ]);
+
+        let id = |name| graph.id(name).unwrap();
+
+        assert_eq!(4, graph.len());
+        assert_eq!("yellow", graph.name(id("yellow")));
+        assert_eq!(&[id("white"), id("yellow")], graph.containers(id("gold")));
+
+        let mut containing: Vec<_> = graph
+            .containing(id("gold"))
+            .into_iter()
+            .map(|colour| graph.name(colour))
+            .collect();
+        containing.sort_unstable();
+        assert_eq!(vec!["red", "white", "yellow"], containing);
+
+        assert_eq!(Ok(1 + 3 + 2 + 8), graph.count_contents(id("red")));
+        assert_eq!(Ok(3), graph.count_contents(id("white")));
+    }
+
+    #[test]
+    fn it_handles_deep_and_wide_graphs() {
+        let names: Vec<_> = (0..10_000).map(|i| format!("colour {}", i)).collect();
+        let graph = BagGraph::new(names.iter().enumerate().map(|(i, name)| {
+            let contents = names
+                .get(i + 1)
+                .into_iter()
+                .chain(names.get(i + 2))
+                .map(|inner| (1, inner.clone()))
+                .collect::<Vec<_>>();
+            (name.clone(), contents)
+        }));
+
+        assert_eq!(
+            9_999,
+            graph.containing(graph.id("colour 9999").unwrap()).len()
+        );
+        assert!(graph
+            .count_contents(graph.id("colour 9950").unwrap())
+            .is_ok());
+
+        let error = graph
+            .count_contents(graph.id("colour 0").unwrap())
+            .unwrap_err();
+        assert!(
+            error.reason.ends_with(" bags hold too many bags to count"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn it_validates_rules() {
+        let graph = graph(&[
+            ("red", &[(1, "blue"), (1, "gold")]),
+            ("blue", &[(2, "green")]),
+            ("green", &[(1, "red"), (1, "green")]),
+            ("gold", &[]),
+            ("gold", &[]),
+            ("gold", &[(1, "white")]),
+        ]);
+
+        assert_eq!(
+            Err(vec![
+                RuleError::Duplicate {
+                    colour: String::from("gold"),
+                    lines: vec![4, 5, 6]
+                },
+                RuleError::Undefined {
+                    colour: String::from("white"),
+                    container: String::from("gold")
+                },
+                RuleError::Cycle(vec![
+                    String::from("red"),
+                    String::from("blue"),
+                    String::from("green")
+                ]),
+            ]),
+            graph.validate()
+        );
+        assert_eq!(
+            vec![
+                "more than one rule for gold bags, on lines 4, 5, 6",
+                "no rule for white bags, found inside gold bags",
+                "bags contain each other: red -> blue -> green -> red"
+            ],
+            graph
+                .validate()
+                .unwrap_err()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+
+        // c's only way back is through b, which a depth first search has
+        // already finished with by the time it reaches c.
+        let crossed = self::graph(&[
+            ("a", &[(1, "b"), (1, "c")]),
+            ("b", &[(1, "a")]),
+            ("c", &[(1, "b")]),
+            ("d", &[(1, "d")]),
+        ]);
+        assert_eq!(
+            Err(vec![
+                RuleError::Cycle(vec![String::from("a"), String::from("b")]),
+                RuleError::Cycle(vec![String::from("d")]),
+            ]),
+            crossed.validate()
+        );
+        assert_eq!(
+            "d bags contain themselves",
+            crossed.validate().unwrap_err()[1].to_string()
+        );
+
+        assert_eq!(
+            Ok(()),
+            self::graph(&[("red", &[(1, "blue")]), ("blue", &[])]).validate()
+        );
+    }
+
+    #[test]
+    fn it_reports_cycles_and_missing_rules() {
+        let cyclic = graph(&[("red", &[(1, "blue")]), ("blue", &[(2, "red")])]);
+        assert_eq!(
+            Err(SolveError::new(7, "red bags eventually contain themselves")),
+            cyclic.count_contents(cyclic.id("red").unwrap())
+        );
+
+        let missing = graph(&[("red", &[(1, "blue")])]);
+        assert_eq!(
+            Err(SolveError::new(7, "no rule for blue bags")),
+            missing.count_contents(missing.id("red").unwrap())
+        );
+
+        let wide = graph(&[("red", &[(usize::MAX, "blue"), (1, "blue")]), ("blue", &[])]);
+        assert_eq!(
+            Err(SolveError::new(7, "red bags hold too many bags to count")),
+            wide.count_contents(wide.id("red").unwrap())
+        );
+    }
+}
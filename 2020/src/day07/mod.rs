@@ -1,77 +1,65 @@
-use anyhow::Result;
-use std::collections::HashMap;
-
-fn get_contents(
-    input: &HashMap<String, Vec<(usize, String)>>,
-    color: &str,
-) -> Vec<(usize, String)> {
-    input
-        .get(color)
-        .unwrap()
-        .iter()
-        .flat_map(|content| std::iter::once(content.clone()).chain(get_contents(input, &content.1)))
-        .collect()
-}
+mod export;
+mod graph;
+mod query;
+mod rule;
 
-fn count_contents(input: &HashMap<String, Vec<(usize, String)>>, color: &str) -> usize {
-    input
-        .get(color)
-        .unwrap()
-        .iter()
-        .map(|(number, color)| number * count_contents(input, &color))
-        .sum::<usize>()
-        + 1
-}
+pub use export::Subgraph;
+pub use graph::{BagGraph, Colour, RuleError};
+pub use rule::{parse_rules, BagRule};
 
-fn parse_bag(input: &str) -> (String, Vec<(usize, String)>) {
-    let parts: Vec<_> = input.split(" bags contain ").collect();
-
-    let color = parts[0].trim().to_owned();
-    let contents = if parts[1] != "no other bags." {
-        parts[1]
-            .split(", ")
-            .map(|s| s.splitn(2, " "))
-            .map(|mut parts| {
-                (
-                    parts.next().unwrap().parse::<usize>().unwrap(),
-                    parts
-                        .next()
-                        .unwrap()
-                        .trim_end_matches(" bag")
-                        .trim_end_matches(" bags")
-                        .trim_end_matches(" bag.")
-                        .trim_end_matches(" bags.")
-                        .to_owned(),
-                )
-            })
-            .collect()
-    } else {
-        Vec::new()
-    };
-
-    (color, contents)
+use crate::{
+    answer::Answer,
+    error::{ParseError, SolveError},
+    runner::{self, Outcome},
+};
+
+/// Parses a set of bag rules, one per line.
+pub fn load(input: &str) -> Result<BagGraph, ParseError> {
+    let rules = parse_rules(input)?;
+    Ok(BagGraph::new(
+        rules.into_iter().map(|rule| (rule.colour, rule.contents)),
+    ))
 }
 
 #[aoc_generator(day7)]
-fn generator(input: &str) -> HashMap<String, Vec<(usize, String)>> {
-    input.lines().map(parse_bag).collect()
+fn generator(input: &str) -> Result<BagGraph, ParseError> {
+    load(input)
+}
+
+const SHINY_GOLD: &str = "shiny gold";
+
+fn validate(graph: &BagGraph) -> Result<(), SolveError> {
+    graph.validate().map_err(|errors| {
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        SolveError::new(7, errors.join("; "))
+    })
 }
 
 #[aoc(day7, part1)]
-fn part1(input: &HashMap<String, Vec<(usize, String)>>) -> usize {
-    input
-        .keys()
-        .filter(|&k| {
-            get_contents(input, k)
-                .iter()
-                .any(|(_, color)| color == "shiny gold")
+fn part1(graph: &BagGraph) -> Answer {
+    validate(graph)
+        .map(|()| {
+            graph
+                .id(SHINY_GOLD)
+                .map_or(0, |colour| graph.containing(colour).len())
         })
-        .count()
+        .into()
 }
 
 #[aoc(day7, part2)]
-fn part2(input: &HashMap<String, Vec<(usize, String)>>) -> usize {
-    count_contents(input, "shiny gold") - 1
+fn part2(graph: &BagGraph) -> Answer {
+    validate(graph)
+        .and_then(|()| {
+            graph
+                .id(SHINY_GOLD)
+                .ok_or_else(|| SolveError::new(7, format!("no rule for {} bags", SHINY_GOLD)))
+        })
+        .and_then(|colour| graph.count_contents(colour))
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(7, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -93,8 +81,8 @@ mod tests {
             dotted black bags contain no other bags.
         "};
 
-        let input = generator(data);
-        assert_eq!(4, part1(&input));
+        let input = generator(data).unwrap();
+        assert_eq!(Answer::Integer(4), part1(&input));
     }
 
     #[test]
@@ -109,7 +97,47 @@ mod tests {
             dark violet bags contain no other bags.
         "};
 
-        let input = generator(data);
-        assert_eq!(126, part2(&input))
+        let input = generator(data).unwrap();
+        assert_eq!(Answer::Integer(126), part2(&input))
+    }
+
+    #[test]
+    fn it_reports_invalid_rules() {
+        let data = indoc! {"
+            faded blue bags contain no other bags.
+            light red bags contain one bright white bag, 2 muted yellow bags.
+        "};
+
+        assert_eq!(
+            ParseError::new(7, 2, 24, "one", "expected a number of bags"),
+            generator(data).unwrap_err()
+        );
+
+        let data = indoc! {"
+            shiny gold bags contain 2 dark red bags.
+        "};
+
+        let input = generator(data).unwrap();
+        assert_eq!(
+            Answer::Failure(String::from(
+                "day 7: no rule for dark red bags, found inside shiny gold bags"
+            )),
+            part2(&input)
+        );
+
+        let data = indoc! {"
+            shiny gold bags contain 2 dark red bags.
+            dark red bags contain no other bags.
+            dark red bags contain 1 shiny gold bag.
+        "};
+
+        let input = generator(data).unwrap();
+        assert_eq!(
+            Answer::Failure(String::from(
+                "day 7: more than one rule for dark red bags, on lines 2, 3; \
+                 bags contain each other: shiny gold -> dark red -> shiny gold"
+            )),
+            part1(&input)
+        );
     }
 }
@@ -0,0 +1,293 @@
+use super::{BagGraph, Colour};
+use crate::error::SolveError;
+use std::collections::VecDeque;
+
+impl BagGraph {
+    /// The colours a `colour` bag eventually holds, outermost first, so every
+    /// colour comes before the colours inside it.
+    fn topological_from(&self, colour: Colour) -> Result<Vec<Colour>, SolveError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Visit {
+            New,
+            Open,
+            Done,
+        }
+
+        let mut visits = vec![Visit::New; self.len()];
+        let mut order = Vec::new();
+        let mut path = vec![(colour, 0)];
+        visits[colour] = Visit::Open;
+
+        while let Some(&mut (colour, ref mut next)) = path.last_mut() {
+            if !self.is_defined(colour) {
+                return Err(SolveError::new(
+                    7,
+                    format!("no rule for {} bags", self.name(colour)),
+                ));
+            }
+
+            match self.contents(colour).get(*next) {
+                Some(&(_, inner)) => {
+                    *next += 1;
+
+                    match visits[inner] {
+                        Visit::New => {
+                            visits[inner] = Visit::Open;
+                            path.push((inner, 0));
+                        }
+                        Visit::Open => {
+                            return Err(SolveError::new(
+                                7,
+                                format!("{} bags eventually contain themselves", self.name(inner)),
+                            ))
+                        }
+                        Visit::Done => {}
+                    }
+                }
+                None => {
+                    visits[colour] = Visit::Done;
+                    order.push(colour);
+                    path.pop();
+                }
+            }
+        }
+
+        order.reverse();
+        Ok(order)
+    }
+
+    /// The fewest bags to open to get from `from` to a `to` bag, as the
+    /// colours along the way including both ends.
+    pub fn shortest_chain(&self, from: Colour, to: Colour) -> Option<Vec<Colour>> {
+        let mut previous = vec![None; self.len()];
+        let mut queue = VecDeque::from(vec![from]);
+
+        while let Some(colour) = queue.pop_front() {
+            if colour == to {
+                let mut chain = vec![to];
+                while let Some(colour) = previous[*chain.last()?] {
+                    chain.push(colour);
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+
+            for &(_, inner) in self.contents(colour) {
+                if inner != from && previous[inner].is_none() {
+                    previous[inner] = Some(colour);
+                    queue.push_back(inner);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The most bags that can be nested between `from` and a `to` bag, as
+    /// the colours along the way including both ends. Ties go to the colour
+    /// listed first in a rule.
+    pub fn longest_chain(
+        &self,
+        from: Colour,
+        to: Colour,
+    ) -> Result<Option<Vec<Colour>>, SolveError> {
+        let order = self.topological_from(from)?;
+        let mut longest: Vec<Option<(usize, Colour)>> = vec![None; self.len()];
+        longest[to] = Some((0, to));
+
+        for &colour in order.iter().rev().filter(|&&colour| colour != to) {
+            longest[colour] = self
+                .contents(colour)
+                .iter()
+                .filter_map(|&(_, inner)| longest[inner].map(|(length, _)| (length + 1, inner)))
+                .fold(None, |longest, (length, inner)| match longest {
+                    Some((best, _)) if best >= length => longest,
+                    _ => Some((length, inner)),
+                });
+        }
+
+        if longest[from].is_none() {
+            return Ok(None);
+        }
+
+        let mut chain = vec![from];
+        let mut colour = from;
+        while colour != to {
+            colour = longest[colour].map_or(to, |(_, next)| next);
+            chain.push(colour);
+        }
+
+        Ok(Some(chain))
+    }
+
+    /// How many levels of bags a `colour` bag holds, 0 for an empty bag.
+    pub fn depth(&self, colour: Colour) -> Result<usize, SolveError> {
+        let order = self.topological_from(colour)?;
+        let mut depths = vec![0; self.len()];
+
+        for &colour in order.iter().rev() {
+            depths[colour] = self
+                .contents(colour)
+                .iter()
+                .map(|&(_, inner)| depths[inner] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+
+        Ok(depths[colour])
+    }
+
+    /// How many bags of each colour a `colour` bag holds once fully unpacked,
+    /// outermost first, or an error if any of them is too many to count in a
+    /// `usize`.
+    pub fn bill_of_materials(&self, colour: Colour) -> Result<Vec<(Colour, usize)>, SolveError> {
+        let order = self.topological_from(colour)?;
+        let mut quantities = vec![0usize; self.len()];
+        quantities[colour] = 1;
+
+        for &outer in &order {
+            for &(number, inner) in self.contents(outer) {
+                quantities[inner] = quantities[outer]
+                    .checked_mul(number)
+                    .and_then(|quantity| quantities[inner].checked_add(quantity))
+                    .ok_or_else(|| {
+                        SolveError::new(
+                            7,
+                            format!("{} bags hold too many bags to count", self.name(colour)),
+                        )
+                    })?;
+            }
+        }
+
+        Ok(order
+            .into_iter()
+            .skip(1)
+            .map(|inner| (inner, quantities[inner]))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day07::load;
+    use indoc::indoc;
+
+    fn graph() -> BagGraph {
+        load(indoc! {"
+            light red bags contain 1 bright white bag, 2 muted yellow bags.
+            dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+            bright white bags contain 1 shiny gold bag.
+            muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+            shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+            dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+            vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+            faded blue bags contain no other bags.
+            dotted black bags contain no other bags.
+        "})
+        .unwrap()
+    }
+
+    fn names<'a>(graph: &'a BagGraph, colours: &[Colour]) -> Vec<&'a str> {
+        colours.iter().map(|&colour| graph.name(colour)).collect()
+    }
+
+    #[test]
+    fn it_finds_chains() {
+        let graph = graph();
+        let id = |name| graph.id(name).unwrap();
+
+        assert_eq!(
+            Some(vec!["light red", "muted yellow", "faded blue"]),
+            graph
+                .shortest_chain(id("light red"), id("faded blue"))
+                .map(|chain| names(&graph, &chain))
+        );
+        assert_eq!(
+            Ok(Some(vec![
+                "light red",
+                "bright white",
+                "shiny gold",
+                "dark olive",
+                "faded blue"
+            ])),
+            graph
+                .longest_chain(id("light red"), id("faded blue"))
+                .map(|chain| chain.map(|chain| names(&graph, &chain)))
+        );
+        assert_eq!(
+            None,
+            graph.shortest_chain(id("faded blue"), id("light red"))
+        );
+        assert_eq!(
+            Ok(None),
+            graph.longest_chain(id("shiny gold"), id("light red"))
+        );
+        assert_eq!(
+            Some(vec![id("shiny gold")]),
+            graph.shortest_chain(id("shiny gold"), id("shiny gold"))
+        );
+    }
+
+    #[test]
+    fn it_measures_depth() {
+        let graph = graph();
+        let id = |name| graph.id(name).unwrap();
+
+        assert_eq!(Ok(4), graph.depth(id("light red")));
+        assert_eq!(Ok(2), graph.depth(id("shiny gold")));
+        assert_eq!(Ok(0), graph.depth(id("faded blue")));
+    }
+
+    #[test]
+    fn it_builds_bills_of_materials() {
+        let graph = graph();
+        let gold = graph.id("shiny gold").unwrap();
+
+        let mut bill: Vec<_> = graph
+            .bill_of_materials(gold)
+            .unwrap()
+            .into_iter()
+            .map(|(colour, quantity)| (graph.name(colour), quantity))
+            .collect();
+        bill.sort_unstable();
+
+        assert_eq!(
+            vec![
+                ("dark olive", 1),
+                ("dotted black", 16),
+                ("faded blue", 13),
+                ("vibrant plum", 2)
+            ],
+            bill
+        );
+        assert_eq!(
+            graph.count_contents(gold),
+            Ok(bill.iter().map(|(_, quantity)| quantity).sum())
+        );
+
+        let huge = load(&format!(
+            "red bags contain {} blue bags.\nblue bags contain 2 white bags.\nwhite bags contain no other bags.\n",
+            usize::MAX
+        ))
+        .unwrap();
+        assert_eq!(
+            Err(SolveError::new(7, "red bags hold too many bags to count")),
+            huge.bill_of_materials(huge.id("red").unwrap())
+        );
+    }
+
+    #[test]
+    fn it_rejects_cycles() {
+        let graph = load(indoc! {"
+            red bags contain 1 blue bag.
+            blue bags contain 1 red bag.
+        "})
+        .unwrap();
+
+        assert_eq!(
+            Err(SolveError::new(7, "red bags eventually contain themselves")),
+            graph.depth(graph.id("red").unwrap())
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::error::ParseError;
+use std::{fmt, str::FromStr};
+
+/// A single line of the rules, such as
+/// `light red bags contain 1 bright white bag, 2 muted yellow bags.`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BagRule {
+    pub colour: String,
+    pub contents: Vec<(usize, String)>,
+}
+
+impl fmt::Display for BagRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bags contain ", self.colour)?;
+
+        if self.contents.is_empty() {
+            return write!(f, "no other bags.");
+        }
+
+        for (i, (number, colour)) in self.contents.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            let bags = if *number == 1 { "bag" } else { "bags" };
+            write!(f, "{} {} {}", number, colour, bags)?;
+        }
+
+        write!(f, ".")
+    }
+}
+
+/// A recursive descent parser over one line of rules.
+///
+/// The grammar is:
+///
+/// ```text
+/// rule     = colour " bags contain " contents "."
+/// contents = "no other bags" | content (", " content)*
+/// content  = number " " colour (" bag" | " bags")
+/// colour   = word (" " word)*
+/// ```
+///
+/// where a word is any run of lowercase letters other than `bag` or `bags`,
+/// and `bag` is used exactly when the number is 1.
+struct Parser<'a> {
+    line: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, text: &'a str, message: impl Into<String>) -> ParseError {
+        ParseError::at(7, self.line, text, message)
+    }
+
+    /// The text up to the next space or punctuation, to point errors at.
+    fn token(&self) -> &'a str {
+        let end = self.rest.find([' ', ',', '.']).unwrap_or(self.rest.len());
+
+        &self.rest[..end.max(self.rest.chars().next().map_or(0, char::len_utf8))]
+    }
+
+    fn eat(&mut self, literal: &str) -> bool {
+        match self.rest.strip_prefix(literal) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), ParseError> {
+        if self.eat(literal) {
+            Ok(())
+        } else {
+            Err(self.error(self.token(), format!("expected `{}`", literal.trim())))
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let end = self.rest.find(|c| !predicate(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    /// Parses a colour and the `bag` or `bags` that follows it.
+    fn colour(&mut self) -> Result<(&'a str, &'a str), ParseError> {
+        let start = self.rest;
+        let mut end = 0;
+
+        loop {
+            let word = self.take_while(|c| c.is_ascii_lowercase());
+
+            match word {
+                "bag" | "bags" if end > 0 => return Ok((&start[..end - 1], word)),
+                "bag" | "bags" => return Err(self.error(word, "expected a colour")),
+                "" => return Err(self.error(self.token(), "expected a colour")),
+                _ => {}
+            }
+
+            if !self.eat(" ") {
+                return Err(self.error(self.token(), "expected ` bag` or ` bags`"));
+            }
+
+            end = start.len() - self.rest.len();
+        }
+    }
+
+    fn content(&mut self) -> Result<(usize, String), ParseError> {
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        let number: usize = digits.parse().map_err(|_| {
+            let text = if digits.is_empty() {
+                self.token()
+            } else {
+                digits
+            };
+            self.error(text, "expected a number of bags")
+        })?;
+
+        self.expect(" ")?;
+        let (colour, bags) = self.colour()?;
+
+        let expected = if number == 1 { "bag" } else { "bags" };
+        if bags != expected {
+            return Err(self.error(bags, format!("expected `{}` after {}", expected, number)));
+        }
+
+        Ok((number, colour.to_owned()))
+    }
+
+    fn rule(&mut self) -> Result<BagRule, ParseError> {
+        let (colour, bags) = self.colour()?;
+        if bags != "bags" {
+            return Err(self.error(bags, "expected `bags`"));
+        }
+
+        self.expect(" contain ")?;
+
+        let mut contents = Vec::new();
+        if !self.eat("no other bags") {
+            loop {
+                contents.push(self.content()?);
+
+                if !self.eat(", ") {
+                    break;
+                }
+            }
+        }
+
+        self.expect(".")?;
+        if !self.rest.is_empty() {
+            return Err(self.error(self.rest, "unexpected text after the rule"));
+        }
+
+        Ok(BagRule {
+            colour: colour.to_owned(),
+            contents,
+        })
+    }
+}
+
+impl FromStr for BagRule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser { line: s, rest: s }.rule()
+    }
+}
+
+/// Parses a set of rules, one per line.
+pub fn parse_rules(input: &str) -> Result<Vec<BagRule>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| line.parse().map_err(|e: ParseError| e.offset(i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn it_round_trips_rules() {
+        let input = indoc! {"
+            light red bags contain 1 bright white bag, 2 muted yellow bags.
+            dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+            bright white bags contain 1 shiny gold bag.
+            faded blue bags contain no other bags.
+            very very pale dusky pink bags contain 12 dark red bags.
+        "};
+
+        let rules = parse_rules(input).unwrap();
+        assert_eq!(
+            BagRule {
+                colour: String::from("light red"),
+                contents: vec![
+                    (1, String::from("bright white")),
+                    (2, String::from("muted yellow"))
+                ],
+            },
+            rules[0]
+        );
+        assert!(rules[3].contents.is_empty());
+        assert_eq!("very very pale dusky pink", rules[4].colour);
+
+        let output: String = rules.iter().map(|rule| format!("{}\n", rule)).collect();
+        assert_eq!(input, output);
+    }
+
+    fn xorshift(state: &mut u64, bound: usize) -> usize {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state as usize % bound
+    }
+
+    fn colour(state: &mut u64) -> String {
+        const WORDS: [&str; 8] = ["shiny", "gold", "dark", "red", "a", "bagel", "bagsy", "no"];
+
+        (0..1 + xorshift(state, 3))
+            .map(|_| WORDS[xorshift(state, WORDS.len())])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn it_round_trips_random_rules() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+
+        for _ in 0..1_000 {
+            let rule = BagRule {
+                colour: colour(&mut state),
+                contents: (0..xorshift(&mut state, 4))
+                    .map(|_| {
+                        let number = xorshift(&mut state, 3) + 10 * xorshift(&mut state, 2);
+                        (number, colour(&mut state))
+                    })
+                    .collect(),
+            };
+
+            assert_eq!(Ok(rule.clone()), rule.to_string().parse());
+        }
+    }
+
+    #[test]
+    fn it_rejects_malformed_rules() {
+        let error = |line: &str| line.parse::<BagRule>().unwrap_err();
+
+        assert_eq!(
+            ParseError::new(7, 1, 39, "bags", "expected `bag` after 1"),
+            error("light red bags contain 1 bright white bags.")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 39, "bag", "expected `bags` after 2"),
+            error("light red bags contain 2 bright white bag.")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 11, "bag", "expected `bags`"),
+            error("light red bag contain no other bags.")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 38, "", "expected `.`"),
+            error("faded blue bags contain no other bags")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 39, "!", "unexpected text after the rule"),
+            error("faded blue bags contain no other bags.!")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 6, ".", "expected ` bag` or ` bags`"),
+            error("faded.")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 1, "bags", "expected a colour"),
+            error("bags contain no other bags.")
+        );
+        assert_eq!(
+            ParseError::new(7, 1, 7, "Blue", "expected a colour"),
+            error("faded Blue bags contain no other bags.")
+        );
+    }
+}
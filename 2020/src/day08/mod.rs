@@ -1,113 +1,51 @@
-use anyhow::{bail, Result};
-use parse_display::{Display, FromStr};
-use std::collections::HashSet;
-
-#[derive(Clone, Copy, Debug, Display, FromStr, PartialEq)]
-enum Sign {
-    #[display("+")]
-    Positive,
-    #[display("-")]
-    Negative,
-}
-
-#[derive(Clone, Copy, Debug, Display, FromStr, PartialEq)]
-#[display(style = "lowercase")]
-enum Op {
-    NOP,
-    ACC,
-    JMP,
-}
-
-#[derive(Clone, Copy, Debug, FromStr, PartialEq)]
-#[from_str(regex = "(?P<op>[a-z]+) (?P<sign>[+-])(?P<value>[0-9]+)")]
-struct Instruction {
-    op: Op,
-    sign: Sign,
-    value: usize,
-}
-
-struct Console {
-    acc: isize,
-    pc: usize,
-    rom: Vec<Instruction>,
-}
-
-impl Console {
-    fn step(&mut self) {
-        match self.rom[self.pc] {
-            Instruction { op: Op::NOP, .. } => self.pc = self.pc.wrapping_add(1),
-            Instruction {
-                op: Op::ACC,
-                sign,
-                value,
-            } => {
-                self.acc = match sign {
-                    Sign::Positive => self.acc.wrapping_add(value as isize),
-                    Sign::Negative => self.acc.wrapping_sub(value as isize),
-                };
-
-                self.pc = self.pc.wrapping_add(1)
-            }
-            Instruction {
-                op: Op::JMP,
-                sign,
-                value,
-            } => match sign {
-                Sign::Positive => self.pc = self.pc.wrapping_add(value),
-                Sign::Negative => self.pc = self.pc.wrapping_sub(value),
-            },
-        }
-    }
-}
+use crate::{
+    answer::Answer,
+    error::{ParseError, SolveError},
+    handheld::{self, Console, Halt, Instruction},
+    runner::{self, Outcome},
+};
 
 fn run_until_loop_or_end(rom: &[Instruction]) -> (isize, bool) {
-    let mut console = Console {
-        acc: 0,
-        pc: 0,
-        rom: rom.to_vec(),
-    };
-    let mut executed_ops = HashSet::new();
-
-    while !executed_ops.contains(&console.pc) && console.pc < rom.len() {
-        executed_ops.insert(console.pc);
-        console.step();
-    }
+    let mut console = Console::new(rom.to_vec());
+    let halt = console.run();
 
-    (console.acc, console.pc >= rom.len())
+    (console.acc(), halt == Halt::Terminated)
 }
 
 #[aoc_generator(day8)]
-fn generator(input: &str) -> Vec<Instruction> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
+fn generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    handheld::load(8, input)
 }
 
 #[aoc(day8, part1)]
-fn part1(rom: &[Instruction]) -> isize {
+fn part1(rom: &[Instruction]) -> Answer {
     let (acc, _) = run_until_loop_or_end(rom);
-    acc
+    acc.into()
+}
+
+fn repair(rom: &[Instruction]) -> Result<isize, SolveError> {
+    let repair = handheld::repair(rom)
+        .ok_or_else(|| SolveError::new(8, "no single nop/jmp swap terminates"))?;
+
+    let mut rom = rom.to_vec();
+    repair.apply(&mut rom);
+
+    Ok(run_until_loop_or_end(&rom).0)
 }
 
 #[aoc(day8, part2)]
-fn part2(rom: &[Instruction]) -> Result<isize> {
-    for i in 0..rom.len() {
-        let mut modified_rom: Vec<Instruction> = rom.to_vec();
-        modified_rom[i].op = match modified_rom[i].op {
-            Op::NOP => Op::JMP,
-            Op::JMP => Op::NOP,
-            _ => continue,
-        };
-
-        if let (acc, true) = run_until_loop_or_end(&modified_rom) {
-            return Ok(acc);
-        }
-    }
+fn part2(rom: &[Instruction]) -> Answer {
+    repair(rom).into()
+}
 
-    bail!("no instruction found")
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(8, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handheld::Op;
     use indoc::indoc;
 
     #[test]
@@ -124,54 +62,18 @@ mod tests {
             acc +6
         "};
 
-        let input = generator(data);
+        let input = generator(data).unwrap();
         assert_eq!(
             [
-                Instruction {
-                    op: Op::NOP,
-                    sign: Sign::Positive,
-                    value: 0
-                },
-                Instruction {
-                    op: Op::ACC,
-                    sign: Sign::Positive,
-                    value: 1
-                },
-                Instruction {
-                    op: Op::JMP,
-                    sign: Sign::Positive,
-                    value: 4
-                },
-                Instruction {
-                    op: Op::ACC,
-                    sign: Sign::Positive,
-                    value: 3
-                },
-                Instruction {
-                    op: Op::JMP,
-                    sign: Sign::Negative,
-                    value: 3
-                },
-                Instruction {
-                    op: Op::ACC,
-                    sign: Sign::Negative,
-                    value: 99
-                },
-                Instruction {
-                    op: Op::ACC,
-                    sign: Sign::Positive,
-                    value: 1
-                },
-                Instruction {
-                    op: Op::JMP,
-                    sign: Sign::Negative,
-                    value: 4
-                },
-                Instruction {
-                    op: Op::ACC,
-                    sign: Sign::Positive,
-                    value: 6
-                },
+                Instruction::new(Op::Nop, 0),
+                Instruction::new(Op::Acc, 1),
+                Instruction::new(Op::Jmp, 4),
+                Instruction::new(Op::Acc, 3),
+                Instruction::new(Op::Jmp, -3),
+                Instruction::new(Op::Acc, -99),
+                Instruction::new(Op::Acc, 1),
+                Instruction::new(Op::Jmp, -4),
+                Instruction::new(Op::Acc, 6),
             ]
             .to_vec(),
             input
@@ -192,8 +94,8 @@ mod tests {
             acc +6
         "};
 
-        let input = generator(data);
-        assert_eq!(5, part1(&input));
+        let input = generator(data).unwrap();
+        assert_eq!(Answer::Integer(5), part1(&input));
     }
 
     #[test]
@@ -210,7 +112,30 @@ mod tests {
             acc +6
         "};
 
-        let input = generator(data);
-        assert_eq!(8, part2(&input).unwrap());
+        let input = generator(data).unwrap();
+        assert_eq!(Answer::Integer(8), part2(&input));
+    }
+
+    #[test]
+    fn it_reports_invalid_instructions() {
+        let data = indoc! {"
+            nop +0
+            acc 1
+        "};
+
+        assert_eq!(
+            ParseError::new(8, 2, 5, "1", "expected a signed argument like `+1`"),
+            generator(data).unwrap_err()
+        );
+        assert_eq!(
+            ParseError::new(
+                8,
+                1,
+                5,
+                "+x",
+                "invalid argument: invalid digit found in string"
+            ),
+            generator("jmp +x").unwrap_err()
+        );
     }
 }
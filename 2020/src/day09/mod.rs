@@ -1,6 +1,13 @@
-use anyhow::Result;
+use crate::{
+    answer::Answer,
+    context::Context,
+    error::{ParseError, SolveError},
+    runner::{self, Outcome},
+};
 use itertools::Itertools;
 
+type Input = Context<Vec<u64>, Option<u64>>;
+
 fn is_valid(value: u64, preamble: &[u64]) -> bool {
     preamble
         .iter()
@@ -24,7 +31,6 @@ fn find_invalid(data: &[u64], len: usize) -> Option<u64> {
 
 fn find_contiguous_range(data: &[u64], value: u64) -> Option<(usize, usize)> {
     (2..data.len())
-        .into_iter()
         .flat_map(|n| {
             data.windows(n)
                 .enumerate()
@@ -41,23 +47,48 @@ fn find_contiguous_min_max(data: &[u64], value: u64) -> Option<(u64, u64)> {
     Some((*min, *max))
 }
 
-#[aoc_generator(day9)]
-fn generator(input: &str) -> Result<Vec<u64>> {
+fn parse(input: &str) -> Result<Vec<u64>, ParseError> {
     input
         .lines()
-        .map(str::parse)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(Into::into)
+        .enumerate()
+        .map(|(i, line)| {
+            line.parse::<u64>()
+                .map_err(|e| ParseError::at(9, line, line, e.to_string()).offset(i))
+        })
+        .collect()
+}
+
+#[aoc_generator(day9)]
+fn generator(input: &str) -> Result<Input, ParseError> {
+    parse(input).map(|data| Context::new(data, |data| find_invalid(data, 25)))
+}
+
+fn invalid_number(input: &Input) -> Result<u64, SolveError> {
+    input
+        .shared()
+        .ok_or_else(|| SolveError::new(9, "every number is the sum of two of the previous 25"))
 }
 
 #[aoc(day9, part1)]
-fn part1(data: &[u64]) -> Option<u64> {
-    find_invalid(data, 25)
+fn part1(input: &Input) -> Answer {
+    invalid_number(input).into()
 }
 
 #[aoc(day9, part2)]
-fn part2(data: &[u64]) -> Option<u64> {
-    find_contiguous_min_max(&data, 556543474).map(|(min, max)| min + max)
+fn part2(input: &Input) -> Answer {
+    invalid_number(input)
+        .and_then(|invalid| {
+            find_contiguous_min_max(input, invalid)
+                .map(|(min, max)| min + max)
+                .ok_or_else(|| {
+                    SolveError::new(9, format!("no contiguous range sums to {}", invalid))
+                })
+        })
+        .into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(9, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -90,7 +121,7 @@ mod tests {
             576
         "};
 
-        let input = generator(data).expect("input to be parsed");
+        let input = parse(data).expect("input to be parsed");
         assert_eq!(
             [
                 35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277,
@@ -126,7 +157,7 @@ mod tests {
             576
         "};
 
-        let input = generator(data).expect("input to be parsed");
+        let input = parse(data).expect("input to be parsed");
         assert_eq!(Some(127), find_invalid(&input, 5));
     }
 
@@ -155,7 +186,24 @@ mod tests {
             576
         "};
 
-        let input = generator(data).expect("input to be parsed");
+        let input = parse(data).expect("input to be parsed");
         assert_eq!(Some((15, 47)), find_contiguous_min_max(&input, 127));
+
+        let input = Context::new(input, |data| find_invalid(data, 5));
+        assert_eq!(Answer::Integer(127), part1(&input));
+        assert_eq!(Answer::Integer(62), part2(&input));
+    }
+
+    #[test]
+    fn it_reports_invalid_numbers() {
+        let data = indoc! {"
+            35
+            -20
+        "};
+
+        assert_eq!(
+            ParseError::new(9, 2, 1, "-20", "invalid digit found in string"),
+            parse(data).unwrap_err()
+        );
     }
 }
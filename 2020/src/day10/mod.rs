@@ -1,34 +1,39 @@
-use anyhow::Result;
+use crate::{
+    answer::Answer,
+    error::{ParseError, SolveError},
+    runner::{self, Outcome},
+};
 use itertools::Itertools;
 use std::collections::HashMap;
 
-fn find_differences(data: &[u64]) -> HashMap<u64, usize> {
-    let max = data.iter().max().unwrap();
+fn find_differences(data: &[u64]) -> Result<HashMap<u64, usize>, SolveError> {
+    let max = data.iter().max().copied().unwrap_or_default();
 
-    [0, max + 3]
-        .iter()
-        .chain(data.into_iter())
-        .sorted()
-        .tuple_windows()
-        .counts_by(|(x, y)| y - x)
+    let chain: Vec<_> = [0, max + 3].iter().chain(data).copied().sorted().collect();
+
+    if let Some((x, y)) = chain.iter().tuple_windows().find(|(&x, &y)| y - x > 3) {
+        return Err(SolveError::new(
+            10,
+            format!("no adapter bridges the gap from {} to {} jolts", x, y),
+        ));
+    }
+
+    Ok(chain.into_iter().tuple_windows().counts_by(|(x, y)| y - x))
 }
 
 fn find_combinations(data: &[u64]) -> usize {
-    let max = data.iter().max().unwrap();
+    let max = data.iter().max().copied().unwrap_or_default();
 
     let mut paths = HashMap::new();
     paths.insert(max + 3, 1);
 
     let paths = [0]
         .iter()
-        .chain(data.into_iter())
+        .chain(data)
         .sorted()
         .rev()
         .fold(paths, |mut result, &n| {
-            let count: usize = (1..=3)
-                .into_iter()
-                .filter_map(|i| result.get(&(n + i)))
-                .sum();
+            let count: usize = (1..=3).filter_map(|i| result.get(&(n + i))).sum();
 
             result.insert(n, count);
             result
@@ -38,23 +43,34 @@ fn find_combinations(data: &[u64]) -> usize {
 }
 
 #[aoc_generator(day10)]
-fn generator(input: &str) -> Result<Vec<u64>> {
+fn generator(input: &str) -> Result<Vec<u64>, ParseError> {
     input
         .lines()
-        .map(str::parse)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(Into::into)
+        .enumerate()
+        .map(|(i, line)| {
+            line.parse::<u64>()
+                .map_err(|e| ParseError::at(10, line, line, e.to_string()).offset(i))
+        })
+        .collect()
 }
 
 #[aoc(day10, part1)]
-fn part1(data: &[u64]) -> usize {
-    let differences = find_differences(data);
-    differences[&1] * differences[&3]
+fn part1(data: &[u64]) -> Answer {
+    find_differences(data)
+        .map(|differences| {
+            let count = |difference| differences.get(&difference).copied().unwrap_or_default();
+            count(1) * count(3)
+        })
+        .into()
 }
 
 #[aoc(day10, part2)]
-fn part2(data: &[u64]) -> usize {
-    find_combinations(data)
+fn part2(data: &[u64]) -> Answer {
+    find_combinations(data).into()
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(10, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -104,7 +120,7 @@ mod tests {
         result.insert(1, 7);
         result.insert(3, 5);
 
-        assert_eq!(result, find_differences(&input));
+        assert_eq!(Ok(result), find_differences(&input));
 
         let data = indoc! {"
             28
@@ -146,7 +162,7 @@ mod tests {
         result.insert(1, 22);
         result.insert(3, 10);
 
-        assert_eq!(result, find_differences(&input));
+        assert_eq!(Ok(result), find_differences(&input));
     }
 
     #[test]
@@ -205,4 +221,24 @@ mod tests {
         let input = generator(data).expect("input to be parsed");
         assert_eq!(19208, find_combinations(&input));
     }
+
+    #[test]
+    fn it_reports_gaps_between_adapters() {
+        let data = indoc! {"
+            1
+            2
+            6
+        "};
+
+        let input = generator(data).expect("input to be parsed");
+        assert_eq!(
+            Err(SolveError::new(
+                10,
+                "no adapter bridges the gap from 2 to 6 jolts"
+            )),
+            find_differences(&input)
+        );
+        assert_eq!(0, find_combinations(&input));
+        assert_eq!(Answer::Integer(0), part1(&[]));
+    }
 }
@@ -1,7 +1,14 @@
-use anyhow::{bail, Context, Result};
-use std::{convert::TryFrom, str::FromStr};
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+use crate::{
+    answer::Answer,
+    automaton::{self, Automaton, Halt},
+    error::{ParseError, SolveError},
+    grid::{Grid, Position, ALL_DIRECTIONS},
+    runner::{self, Outcome},
+};
+use anyhow::{bail, Result};
+use std::convert::TryFrom;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum Tile {
     Floor,
     Seat(bool),
@@ -20,128 +27,56 @@ impl TryFrom<char> for Tile {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Grid {
-    width: usize,
-    height: usize,
-    tiles: Vec<Tile>,
-}
-
-impl FromStr for Grid {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        let width = s
-            .lines()
-            .next()
-            .context("couldn't determine grid width")?
-            .len();
-        let height = s.lines().count();
-
-        s.lines()
-            .flat_map(str::chars)
-            .map(Tile::try_from)
-            .collect::<Result<_, _>>()
-            .map(|tiles| Grid {
-                width,
-                height,
-                tiles,
-            })
+impl From<Tile> for char {
+    fn from(tile: Tile) -> Self {
+        match tile {
+            Tile::Floor => '.',
+            Tile::Seat(false) => 'L',
+            Tile::Seat(true) => '#',
+        }
     }
 }
 
-impl Grid {
-    fn get(&self, x: i32, y: i32) -> Option<Tile> {
-        let x = usize::try_from(x).ok()?;
-        let y = usize::try_from(y).ok()?;
-
-        (x < self.width && y < self.height).then(|| self.tiles[y * self.width + x])
-    }
-
-    fn neighbouring_seats(&self, index: usize) -> impl Iterator<Item = Tile> + '_ {
-        let y = (index / self.width) as i32;
-        let x = (index % self.width) as i32;
-
-        [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ]
-        .iter()
-        .map(move |(dx, dy)| (x + dx, y + dy))
-        .filter_map(move |(x, y)| self.get(x, y))
+impl Grid<Tile> {
+    fn neighbouring_seats(&self, position: Position) -> impl Iterator<Item = Tile> + '_ {
+        self.neighbours8(position).map(|(_, &tile)| tile)
     }
 
     fn occupied_seats(&self) -> usize {
-        self.tiles
-            .iter()
-            .filter(|tile| matches!(tile, Tile::Seat(true)))
-            .count()
+        self.count(|tile| matches!(tile, Tile::Seat(true)))
     }
 
-    fn visible_seats(&self, index: usize) -> impl Iterator<Item = Tile> + '_ {
-        let y = (index / self.width) as i32;
-        let x = (index % self.width) as i32;
-
-        [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ]
-        .iter()
-        .map(move |(dx, dy)| {
-            itertools::iterate((x + dx, y + dy), move |(x, y)| (x + dx, y + dy))
-                .map_while(move |(x, y)| self.get(x, y))
-                .skip_while(|t| matches!(t, Tile::Floor))
-                .take(1)
+    fn visible_seats(&self, position: Position) -> impl Iterator<Item = Tile> + '_ {
+        ALL_DIRECTIONS.iter().filter_map(move |&direction| {
+            self.ray(position, direction)
+                .map(|(_, &tile)| tile)
+                .find(|tile| !matches!(tile, Tile::Floor))
         })
-        .flatten()
     }
 
-    fn into_iter(self, f: fn(&Grid, usize, Tile) -> Tile) -> GridIterator {
-        GridIterator { current: self, f }
+    fn automaton(
+        self,
+        rule: fn(&Grid<Tile>, Position, Tile) -> Tile,
+    ) -> Automaton<Grid<Tile>, impl FnMut(&Grid<Tile>) -> Grid<Tile>> {
+        automaton::cellular(self, move |grid, position, &tile| {
+            rule(grid, position, tile)
+        })
     }
 }
 
-struct GridIterator {
-    current: Grid,
-    f: fn(&Grid, usize, Tile) -> Tile,
-}
+fn settle(data: &Grid<Tile>, rule: fn(&Grid<Tile>, Position, Tile) -> Tile) -> Answer {
+    let mut automaton = data.clone().automaton(rule);
 
-impl Iterator for GridIterator {
-    type Item = Grid;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let next_tiles = self
-            .current
-            .tiles
-            .iter()
-            .enumerate()
-            .map(|(index, &tile)| (self.f)(&self.current, index, tile))
-            .collect();
-
-        if self.current.tiles != next_tiles {
-            self.current.tiles = next_tiles;
-            Some(self.current.clone())
-        } else {
-            None
-        }
+    match automaton.run() {
+        Halt::FixedPoint { .. } => Ok(automaton.current().occupied_seats()),
+        _ => Err(SolveError::new(11, "the seating never stabilises")),
     }
+    .into()
 }
 
-fn part1_visibility(grid: &Grid, index: usize, tile: Tile) -> Tile {
+fn part1_visibility(grid: &Grid<Tile>, position: Position, tile: Tile) -> Tile {
     let occupied = grid
-        .neighbouring_seats(index)
+        .neighbouring_seats(position)
         .filter(|n| matches!(n, Tile::Seat(true)))
         .count();
 
@@ -152,9 +87,9 @@ fn part1_visibility(grid: &Grid, index: usize, tile: Tile) -> Tile {
     }
 }
 
-fn part2_visibility(grid: &Grid, index: usize, tile: Tile) -> Tile {
+fn part2_visibility(grid: &Grid<Tile>, position: Position, tile: Tile) -> Tile {
     let occupied = grid
-        .visible_seats(index)
+        .visible_seats(position)
         .filter(|n| matches!(n, Tile::Seat(true)))
         .count();
 
@@ -166,24 +101,22 @@ fn part2_visibility(grid: &Grid, index: usize, tile: Tile) -> Tile {
 }
 
 #[aoc_generator(day11)]
-fn generator(input: &str) -> Result<Grid> {
-    Grid::from_str(&input)
+fn generator(input: &str) -> Result<Grid<Tile>, ParseError> {
+    Grid::parse(11, input)
 }
 
 #[aoc(day11, part1)]
-fn part1(data: &Grid) -> Option<usize> {
-    data.clone()
-        .into_iter(part1_visibility)
-        .last()
-        .map(|grid| grid.occupied_seats())
+fn part1(data: &Grid<Tile>) -> Answer {
+    settle(data, part1_visibility)
 }
 
 #[aoc(day11, part2)]
-fn part2(data: &Grid) -> Option<usize> {
-    data.clone()
-        .into_iter(part2_visibility)
-        .last()
-        .map(|grid| grid.occupied_seats())
+fn part2(data: &Grid<Tile>) -> Answer {
+    settle(data, part2_visibility)
+}
+
+pub(crate) fn run(input: &str) -> Vec<Outcome> {
+    runner::run(11, input, generator, &[part1, part2])
 }
 
 #[cfg(test)]
@@ -211,10 +144,10 @@ mod tests {
         .expect("input to be parsed");
 
         assert_eq!(
-            Grid {
-                width: 10,
-                height: 10,
-                tiles: vec![
+            Grid::new(
+                10,
+                10,
+                vec![
                     Seat(false),
                     Floor,
                     Seat(false),
@@ -316,7 +249,7 @@ mod tests {
                     Seat(false),
                     Seat(false)
                 ]
-            },
+            ),
             data
         );
     }
@@ -339,7 +272,7 @@ mod tests {
         ))
         .expect("input to be parsed");
 
-        let mut grid = data.into_iter(part1_visibility);
+        let mut grid = data.automaton(part1_visibility);
 
         assert_eq!(
             generator(indoc!(
@@ -455,7 +388,7 @@ mod tests {
         ))
         .expect("input to be parsed");
 
-        let mut grid = data.into_iter(part2_visibility);
+        let mut grid = data.automaton(part2_visibility);
 
         assert_eq!(
             generator(indoc!(
@@ -571,4 +504,23 @@ mod tests {
             grid.next()
         );
     }
+
+    #[test]
+    fn it_reports_invalid_grids() {
+        assert_eq!(
+            Err(ParseError::new(11, 2, 3, "x", "invalid character: x")),
+            generator("L.L\nL.x")
+        );
+        assert_eq!(
+            Err(ParseError::new(11, 2, 1, "L.", "expected a row of width 3")),
+            generator("L.L\nL.")
+        );
+    }
+
+    #[test]
+    fn it_counts_seats_in_a_stable_grid() {
+        let data = generator("#.#\n...").expect("input to be parsed");
+
+        assert_eq!(Answer::Integer(2), part1(&data));
+    }
 }
@@ -0,0 +1,121 @@
+use std::{error, fmt};
+
+/// A malformed puzzle input, pointing at the offending text.
+///
+/// Lines and columns are 1-based, and columns count characters rather than
+/// bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(
+        day: u32,
+        line: usize,
+        column: usize,
+        text: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        ParseError {
+            day,
+            line,
+            column,
+            text: text.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Builds an error for `text`, a slice of `source`, working out its line
+    /// and column from where it sits in `source`.
+    ///
+    /// If `text` doesn't point into `source` the error is placed at the start.
+    pub fn at(day: u32, source: &str, text: &str, message: impl Into<String>) -> Self {
+        let start = source.as_ptr() as usize;
+        let offset = (text.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|&offset| offset <= source.len() && source.is_char_boundary(offset))
+            .unwrap_or(0);
+
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        ParseError::new(day, line, column, text, message)
+    }
+
+    /// Moves the error down by `lines`, for errors raised while parsing a
+    /// chunk that doesn't start on the first line of the input.
+    pub fn offset(mut self, lines: usize) -> Self {
+        self.line += lines;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, line {}, column {}: {}: {:?}",
+            self.day, self.line, self.column, self.message, self.text
+        )
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// A well-formed input that has no answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolveError {
+    pub day: u32,
+    pub reason: String,
+}
+
+impl SolveError {
+    pub fn new(day: u32, reason: impl Into<String>) -> Self {
+        SolveError {
+            day,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {}: {}", self.day, self.reason)
+    }
+}
+
+impl error::Error for SolveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_locates_text_in_the_source() {
+        let source = "nop +0\nacc +1\njmp ¬4";
+        let text = &source[source.find('¬').unwrap()..];
+
+        assert_eq!(
+            ParseError::new(8, 3, 5, "¬4", "invalid sign"),
+            ParseError::at(8, source, text, "invalid sign")
+        );
+    }
+
+    #[test]
+    fn it_offsets_errors() {
+        let error = ParseError::at(4, "byr", "byr", "missing value").offset(9);
+
+        assert_eq!((10, 1), (error.line, error.column));
+        assert_eq!(
+            "day 4, line 10, column 1: missing value: \"byr\"",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use crate::error::ParseError;
+use std::{convert::TryFrom, fmt, ops::Index};
+
+pub type Position = (usize, usize);
+pub type Direction = (isize, isize);
+
+pub const ORTHOGONAL: [Direction; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub const ALL_DIRECTIONS: [Direction; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid of cells stored row by row, with `(0, 0)` at the top
+/// left.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(width * height, cells.len(), "grid size doesn't match cells");
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parses a grid with one cell per character and one row per line.
+    pub fn parse(day: u32, s: &str) -> Result<Self, ParseError>
+    where
+        T: TryFrom<char>,
+        T::Error: fmt::Display,
+    {
+        let width = s.lines().next().map_or(0, |line| line.chars().count());
+        if width == 0 {
+            return Err(ParseError::at(day, s, s, "grid is empty"));
+        }
+
+        if let Some(line) = s.lines().find(|line| line.chars().count() != width) {
+            return Err(ParseError::at(
+                day,
+                s,
+                line,
+                format!("expected a row of width {}", width),
+            ));
+        }
+
+        let height = s.lines().count();
+
+        s.lines()
+            .flat_map(|line| line.char_indices().map(move |(i, c)| (line, i, c)))
+            .map(|(line, i, c)| {
+                T::try_from(c)
+                    .map_err(|e| ParseError::at(day, s, &line[i..i + c.len_utf8()], e.to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(|cells| Grid::new(width, height, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        let x = usize::try_from(x).ok()?;
+        let y = usize::try_from(y).ok()?;
+
+        (x < self.width && y < self.height).then(|| &self.cells[y * self.width + x])
+    }
+
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        let x = usize::try_from(x).ok()?;
+        let y = usize::try_from(y).ok()?;
+
+        if x < self.width && y < self.height {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Looks up a cell as if the grid repeated forever in every direction.
+    pub fn get_wrapping(&self, x: isize, y: isize) -> Option<&T> {
+        if self.cells.is_empty() {
+            return None;
+        }
+
+        let x = x.rem_euclid(self.width as isize);
+        let y = y.rem_euclid(self.height as isize);
+
+        self.get(x, y)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| (i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn count(&self, predicate: impl Fn(&T) -> bool) -> usize {
+        self.cells.iter().filter(|&cell| predicate(cell)).count()
+    }
+
+    pub fn map<U>(&self, f: impl Fn(Position, &T) -> U) -> Grid<U> {
+        Grid::new(
+            self.width,
+            self.height,
+            self.iter()
+                .map(|(position, cell)| f(position, cell))
+                .collect(),
+        )
+    }
+
+    /// The cells found by stepping from `(x, y)` in `direction`, excluding
+    /// the starting cell, up to the edge of the grid.
+    pub fn ray(
+        &self,
+        (x, y): Position,
+        (dx, dy): Direction,
+    ) -> impl Iterator<Item = (Position, &T)> + '_ {
+        let (x, y) = (x as isize, y as isize);
+
+        (1..).map_while(move |n| {
+            let (x, y) = (x + dx * n, y + dy * n);
+            self.get(x, y).map(|cell| ((x as usize, y as usize), cell))
+        })
+    }
+
+    pub fn neighbours<'a>(
+        &'a self,
+        position: Position,
+        directions: &'a [Direction],
+    ) -> impl Iterator<Item = (Position, &'a T)> + 'a {
+        directions
+            .iter()
+            .filter_map(move |&direction| self.ray(position, direction).next())
+    }
+
+    pub fn neighbours4(&self, position: Position) -> impl Iterator<Item = (Position, &T)> {
+        self.neighbours(position, &ORTHOGONAL)
+    }
+
+    pub fn neighbours8(&self, position: Position) -> impl Iterator<Item = (Position, &T)> {
+        self.neighbours(position, &ALL_DIRECTIONS)
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        let cells = if x < self.width {
+            &self.cells[x..]
+        } else {
+            &[]
+        };
+        cells.iter().step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.rebuild(self.height, self.width, |(x, y)| (y, x))
+    }
+
+    pub fn rotate_clockwise(&self) -> Self
+    where
+        T: Clone,
+    {
+        let height = self.height;
+        self.rebuild(self.height, self.width, |(x, y)| (y, height - 1 - x))
+    }
+
+    pub fn rotate_counterclockwise(&self) -> Self
+    where
+        T: Clone,
+    {
+        let width = self.width;
+        self.rebuild(self.height, self.width, |(x, y)| (width - 1 - y, x))
+    }
+
+    /// Builds a grid of the given size where each cell is copied from the
+    /// position `source` gives in this grid.
+    fn rebuild(&self, width: usize, height: usize, source: impl Fn(Position) -> Position) -> Self
+    where
+        T: Clone,
+    {
+        let cells = (0..width * height)
+            .map(|i| self[source((i % width, i / width))].clone())
+            .collect();
+
+        Grid::new(width, height, cells)
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): Position) -> &Self::Output {
+        assert!(x < self.width, "x out of bounds");
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> fmt::Display for Grid<T>
+where
+    T: Copy + Into<char>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            let line: String = row.iter().map(|&cell| cell.into()).collect();
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Cell(char);
+
+    impl TryFrom<char> for Cell {
+        type Error = String;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            if value.is_ascii_lowercase() {
+                Ok(Cell(value))
+            } else {
+                Err(format!("invalid character: {}", value))
+            }
+        }
+    }
+
+    impl From<Cell> for char {
+        fn from(cell: Cell) -> Self {
+            cell.0
+        }
+    }
+
+    fn grid() -> Grid<Cell> {
+        Grid::parse(
+            0,
+            indoc! {"
+                abc
+                def
+            "},
+        )
+        .unwrap()
+    }
+
+    fn chars<'a>(cells: impl Iterator<Item = (Position, &'a Cell)>) -> String {
+        cells.map(|(_, cell)| cell.0).collect()
+    }
+
+    #[test]
+    fn it_parses_a_grid() {
+        let grid = grid();
+
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(Some(&Cell('f')), grid.get(2, 1));
+        assert_eq!(None, grid.get(3, 1));
+        assert_eq!(None, grid.get(-1, 0));
+        assert_eq!(Cell('d'), grid[(0, 1)]);
+        assert_eq!("abc\ndef\n", grid.to_string());
+    }
+
+    #[test]
+    fn it_reports_invalid_grids() {
+        assert_eq!(
+            Err(ParseError::new(0, 2, 2, "E", "invalid character: E")),
+            Grid::<Cell>::parse(0, "abc\ndEf")
+        );
+        assert_eq!(
+            Err(ParseError::new(0, 2, 1, "de", "expected a row of width 3")),
+            Grid::<Cell>::parse(0, "abc\nde")
+        );
+        assert_eq!(
+            Err(ParseError::new(0, 1, 1, "", "grid is empty")),
+            Grid::<Cell>::parse(0, "")
+        );
+    }
+
+    #[test]
+    fn it_wraps_around() {
+        let grid = grid();
+
+        assert_eq!(Some(&Cell('a')), grid.get_wrapping(3, 2));
+        assert_eq!(Some(&Cell('f')), grid.get_wrapping(-1, -1));
+        assert_eq!(Some(&Cell('e')), grid.get_wrapping(7, 5));
+    }
+
+    #[test]
+    fn it_finds_neighbours() {
+        let grid = grid();
+
+        assert_eq!("cea", chars(grid.neighbours4((1, 0))));
+        assert_eq!("bfd", chars(grid.neighbours4((1, 1))));
+        assert_eq!("bde", chars(grid.neighbours8((0, 0))));
+        assert_eq!("abcdf", chars(grid.neighbours8((1, 1))));
+    }
+
+    #[test]
+    fn it_casts_rays() {
+        let grid = grid();
+
+        assert_eq!("bc", chars(grid.ray((0, 0), (1, 0))));
+        assert_eq!("e", chars(grid.ray((0, 0), (1, 1))));
+        assert_eq!(
+            vec![(1, 1), (2, 1)],
+            grid.ray((0, 1), (1, 0)).map(|(p, _)| p).collect::<Vec<_>>()
+        );
+        assert_eq!("", chars(grid.ray((2, 1), (1, 0))));
+    }
+
+    #[test]
+    fn it_iterates_rows_and_columns() {
+        let grid = grid();
+
+        assert_eq!(Some(&[Cell('d'), Cell('e'), Cell('f')][..]), grid.row(1));
+        assert_eq!(None, grid.row(2));
+        assert_eq!(2, grid.rows().count());
+        assert_eq!(
+            vec!["ad", "be", "cf"],
+            grid.columns()
+                .map(|column| column.map(|cell| cell.0).collect::<String>())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, grid.column(3).count());
+    }
+
+    #[test]
+    fn it_transforms_grids() {
+        let grid = grid();
+
+        assert_eq!("ad\nbe\ncf\n", grid.transpose().to_string());
+        assert_eq!("da\neb\nfc\n", grid.rotate_clockwise().to_string());
+        assert_eq!("cf\nbe\nad\n", grid.rotate_counterclockwise().to_string());
+        assert_eq!(
+            grid,
+            grid.rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+        );
+    }
+
+    #[test]
+    fn it_maps_and_counts_cells() {
+        let grid = grid();
+        let vowels = grid.map(|_, cell| matches!(cell.0, 'a' | 'e' | 'i' | 'o' | 'u'));
+
+        assert_eq!(2, vowels.count(|&vowel| vowel));
+        assert_eq!(Some(&true), vowels.get(1, 1));
+    }
+}
@@ -0,0 +1,252 @@
+use super::{Instruction, Op};
+use crate::error::ParseError;
+use std::collections::HashMap;
+
+enum Argument<'a> {
+    Value(isize),
+    Label(&'a str),
+}
+
+struct Line<'a> {
+    op: Op,
+    argument: Argument<'a>,
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assembles a program, resolving labels to relative offsets.
+///
+/// Each line holds an optional `label:`, an optional instruction and an
+/// optional `; comment`. An argument is either a signed number or the name of
+/// a label, so `jmp loop` jumps to wherever `loop:` is defined. A label on a
+/// line of its own refers to the next instruction.
+pub fn assemble(day: u32, source: &str) -> Result<Vec<Instruction>, ParseError> {
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+
+    for line in source.lines() {
+        let code = line.split(';').next().unwrap_or("");
+
+        let code = match code.split_once(':') {
+            Some((label, rest)) => {
+                let label = label.trim();
+                if !is_label(label) {
+                    return Err(ParseError::at(
+                        day,
+                        source,
+                        label,
+                        "expected a label like `loop:`",
+                    ));
+                }
+
+                if labels.insert(label, lines.len()).is_some() {
+                    return Err(ParseError::at(
+                        day,
+                        source,
+                        label,
+                        "label is already defined",
+                    ));
+                }
+
+                rest
+            }
+            None => code,
+        };
+
+        let code = code.trim();
+        if code.is_empty() {
+            continue;
+        }
+
+        let mut words = code.split_whitespace();
+        let (op, argument) = match (words.next(), words.next(), words.next()) {
+            (Some(op), Some(argument), None) => (op, argument),
+            _ => {
+                return Err(ParseError::at(
+                    day,
+                    source,
+                    code,
+                    "expected an instruction like `acc +1` or `jmp label`",
+                ))
+            }
+        };
+
+        let op = op
+            .parse()
+            .map_err(|message: String| ParseError::at(day, source, op, message))?;
+
+        let argument = if argument.starts_with(['+', '-']) {
+            argument.parse().map(Argument::Value).map_err(|e| {
+                ParseError::at(day, source, argument, format!("invalid argument: {}", e))
+            })?
+        } else if is_label(argument) {
+            Argument::Label(argument)
+        } else {
+            return Err(ParseError::at(
+                day,
+                source,
+                argument,
+                "expected a signed argument or a label",
+            ));
+        };
+
+        lines.push(Line { op, argument });
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let arg = match line.argument {
+                Argument::Value(value) => value,
+                Argument::Label(label) => match labels.get(label) {
+                    Some(&target) => target as isize - i as isize,
+                    None => {
+                        return Err(ParseError::at(
+                            day,
+                            source,
+                            label,
+                            format!("undefined label `{}`", label),
+                        ))
+                    }
+                },
+            };
+
+            Ok(Instruction::new(line.op, arg))
+        })
+        .collect()
+}
+
+/// Prints a program in the canonical form [`load`](super::load) reads, one
+/// instruction per line with an explicit sign on every argument.
+pub fn disassemble(rom: &[Instruction]) -> String {
+    rom.iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handheld::load;
+    use indoc::indoc;
+
+    #[test]
+    fn it_resolves_labels() {
+        let rom = assemble(
+            0,
+            indoc! {"
+                ; never gets past the loop
+                start:  acc +1     ; bump
+                        nop +0
+                loop:
+                        acc +3
+                        jmp loop   ; spin forever
+                        jmp start
+                end:    jmp end
+            "},
+        )
+        .unwrap();
+
+        assert_eq!(
+            indoc! {"
+                acc +1
+                nop +0
+                acc +3
+                jmp -1
+                jmp -4
+                jmp +0
+            "},
+            disassemble(&rom)
+        );
+    }
+
+    #[test]
+    fn it_reports_bad_assembly() {
+        assert_eq!(
+            Err(ParseError::new(
+                0,
+                2,
+                5,
+                "nowhere",
+                "undefined label `nowhere`"
+            )),
+            assemble(0, "a: nop +0\njmp nowhere")
+        );
+        assert_eq!(
+            Err(ParseError::new(0, 2, 1, "a", "label is already defined")),
+            assemble(0, "a: nop +0\na: nop +0")
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                0,
+                1,
+                1,
+                "1a",
+                "expected a label like `loop:`"
+            )),
+            assemble(0, "1a: nop +0")
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                0,
+                1,
+                5,
+                "1",
+                "expected a signed argument or a label"
+            )),
+            assemble(0, "acc 1 ; no sign")
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                0,
+                1,
+                5,
+                "+9223372036854775808",
+                "invalid argument: number too large to fit in target type"
+            )),
+            assemble(0, "jmp +9223372036854775808")
+        );
+    }
+
+    /// A small xorshift generator, so the round trip can be checked against
+    /// many programs without pulling in a property testing crate.
+    fn programs() -> impl Iterator<Item = Vec<Instruction>> {
+        let mut state = 0x2020_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        (0..200).map(move |_| {
+            let len = next() % 20;
+            (0..len)
+                .map(|_| {
+                    let op = Op::ALL[(next() % 3) as usize];
+                    let arg = (next() % 2001) as isize - 1000;
+                    Instruction::new(op, arg)
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn it_round_trips() {
+        for rom in programs() {
+            let text = disassemble(&rom);
+
+            assert_eq!(Ok(rom.clone()), assemble(0, &text));
+            assert_eq!(text, disassemble(&assemble(0, &text).unwrap()));
+            assert_eq!(Ok(rom), load(0, text.trim_end_matches('\n')));
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use super::{Instruction, Op, Trace};
+use std::{convert::TryFrom, fmt::Write};
+
+/// Where control goes when a block finishes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target {
+    Block(usize),
+    /// Just past the last instruction, so the program terminates.
+    End,
+    /// Outside the program, or `None` if the jump overflows an `isize`.
+    OutOfBounds(Option<isize>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EdgeKind {
+    Jump,
+    Fallthrough,
+}
+
+/// A run of instructions that always execute together, `start..end`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub next: Target,
+    pub kind: EdgeKind,
+    pub reachable: bool,
+    pub executed: bool,
+    pub looping: bool,
+}
+
+/// The control-flow graph of a program, annotated with what actually runs.
+///
+/// Every block ends in a single edge, so an edge is executed or part of the
+/// loop exactly when the block it leaves is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ControlFlowGraph<'a> {
+    rom: &'a [Instruction],
+    pub blocks: Vec<Block>,
+    /// The first instruction to run twice, if the program loops.
+    pub loop_start: Option<usize>,
+}
+
+fn target(rom: &[Instruction], pc: Option<isize>) -> Option<usize> {
+    pc.and_then(|pc| usize::try_from(pc).ok())
+        .filter(|&pc| pc <= rom.len())
+}
+
+fn offset(pc: usize, arg: isize) -> Option<isize> {
+    isize::try_from(pc).ok().and_then(|pc| pc.checked_add(arg))
+}
+
+impl<'a> ControlFlowGraph<'a> {
+    pub fn new(rom: &'a [Instruction]) -> Self {
+        let mut leader = vec![false; rom.len()];
+        if let Some(first) = leader.first_mut() {
+            *first = true;
+        }
+
+        for (pc, instruction) in rom.iter().enumerate() {
+            if instruction.op == Op::Jmp {
+                for next in [offset(pc, instruction.arg), offset(pc, 1)] {
+                    if let Some(next) = target(rom, next).filter(|&next| next < rom.len()) {
+                        leader[next] = true;
+                    }
+                }
+            }
+        }
+
+        let starts: Vec<_> = (0..rom.len()).filter(|&pc| leader[pc]).collect();
+        let mut block_of = vec![0; rom.len()];
+        for (i, &start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(rom.len());
+            block_of[start..end].iter_mut().for_each(|block| *block = i);
+        }
+
+        let to_target = |pc: Option<isize>| match target(rom, pc) {
+            Some(pc) if pc == rom.len() => Target::End,
+            Some(pc) => Target::Block(block_of[pc]),
+            None => Target::OutOfBounds(pc),
+        };
+
+        let mut blocks: Vec<_> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(rom.len());
+                let last = rom[end - 1];
+                let (next, kind) = match last.op {
+                    Op::Jmp => (offset(end - 1, last.arg), EdgeKind::Jump),
+                    Op::Nop | Op::Acc => (offset(end, 0), EdgeKind::Fallthrough),
+                };
+
+                Block {
+                    start,
+                    end,
+                    next: to_target(next),
+                    kind,
+                    reachable: false,
+                    executed: false,
+                    looping: false,
+                }
+            })
+            .collect();
+
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            match blocks.get_mut(i) {
+                Some(block) if !block.reachable => {
+                    block.reachable = true;
+                    if let Target::Block(next) = block.next {
+                        stack.push(next);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The loop is whatever the trace's loop report says, so the two can
+        // never disagree about it.
+        let trace = Trace::record(rom);
+        let report = trace.loop_report();
+
+        for step in &trace.steps {
+            blocks[block_of[step.pc]].executed = true;
+        }
+        for step in report.map_or(&[][..], |report| report.cycle) {
+            blocks[block_of[step.pc]].looping = true;
+        }
+
+        let loop_start = report.map(|report| report.repeated);
+
+        ControlFlowGraph {
+            rom,
+            blocks,
+            loop_start,
+        }
+    }
+
+    pub fn unreachable(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().filter(|block| !block.reachable)
+    }
+
+    fn lines(&self, block: &Block) -> impl Iterator<Item = String> + '_ {
+        (block.start..block.end).map(move |pc| format!("{}: {}", pc, self.rom[pc]))
+    }
+
+    fn node(i: usize, block: &Block) -> String {
+        match block.next {
+            Target::Block(next) => format!("b{}", next),
+            Target::End => String::from("exit"),
+            Target::OutOfBounds(_) => format!("out{}", i),
+        }
+    }
+
+    fn describe(pc: Option<isize>) -> String {
+        pc.map_or_else(|| String::from("overflow"), |pc| pc.to_string())
+    }
+
+    fn label(kind: EdgeKind) -> &'static str {
+        match kind {
+            EdgeKind::Jump => "jmp",
+            EdgeKind::Fallthrough => "next",
+        }
+    }
+
+    /// Renders the graph in Graphviz DOT, with the executed path in blue, the
+    /// infinite loop in red and unreachable blocks dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph program {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        dot.push_str("    start [shape=point];\n");
+        dot.push_str("    exit [shape=doublecircle, label=\"end\"];\n");
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            let label: String = self.lines(block).map(|line| line + "\\l").collect();
+            let style = if block.looping {
+                ", color=red, penwidth=2"
+            } else if block.executed {
+                ", color=blue"
+            } else if !block.reachable {
+                ", style=dashed, fontcolor=gray"
+            } else {
+                ""
+            };
+
+            writeln!(dot, "    b{} [label=\"{}\"{}];", i, label, style).unwrap();
+
+            if let Target::OutOfBounds(pc) = block.next {
+                writeln!(
+                    dot,
+                    "    out{} [shape=octagon, label=\"out of bounds: {}\"];",
+                    i,
+                    Self::describe(pc)
+                )
+                .unwrap();
+            }
+        }
+
+        if self.blocks.is_empty() {
+            dot.push_str("    start -> exit;\n");
+        } else {
+            dot.push_str("    start -> b0 [color=blue];\n");
+        }
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            let style = if block.looping {
+                ", color=red, penwidth=2"
+            } else if block.executed {
+                ", color=blue"
+            } else {
+                ""
+            };
+
+            writeln!(
+                dot,
+                "    b{} -> {} [label=\"{}\"{}];",
+                i,
+                Self::node(i, block),
+                Self::label(block.kind),
+                style
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a Mermaid flowchart, styled like
+    /// [`ControlFlowGraph::to_dot`].
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n");
+        mermaid.push_str("    start((start))\n");
+        mermaid.push_str("    exit(((end)))\n");
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            let label = self.lines(block).collect::<Vec<_>>().join("<br/>");
+            writeln!(mermaid, "    b{}[\"{}\"]", i, label).unwrap();
+
+            if let Target::OutOfBounds(pc) = block.next {
+                writeln!(
+                    mermaid,
+                    "    out{}{{{{\"out of bounds: {}\"}}}}",
+                    i,
+                    Self::describe(pc)
+                )
+                .unwrap();
+            }
+        }
+
+        let first = if self.blocks.is_empty() { "exit" } else { "b0" };
+        writeln!(mermaid, "    start --> {}", first).unwrap();
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(
+                mermaid,
+                "    b{} -->|{}| {}",
+                i,
+                Self::label(block.kind),
+                Self::node(i, block)
+            )
+            .unwrap();
+        }
+
+        mermaid.push_str("    classDef executed stroke:blue\n");
+        mermaid.push_str("    classDef looping stroke:red,stroke-width:2px\n");
+        mermaid.push_str("    classDef unreachable stroke-dasharray:5 5,color:gray\n");
+
+        for class in ["looping", "executed", "unreachable"] {
+            let nodes: Vec<_> = self
+                .blocks
+                .iter()
+                .enumerate()
+                .filter(|(_, block)| match class {
+                    "looping" => block.looping,
+                    "executed" => block.executed && !block.looping,
+                    _ => !block.reachable,
+                })
+                .map(|(i, _)| format!("b{}", i))
+                .collect();
+
+            if !nodes.is_empty() {
+                writeln!(mermaid, "    class {} {}", nodes.join(","), class).unwrap();
+            }
+        }
+
+        // Link 0 is the entry edge, so each block's edge is one after its index.
+        for (i, block) in self.blocks.iter().enumerate() {
+            let stroke = if block.looping {
+                "stroke:red,stroke-width:2px"
+            } else if block.executed {
+                "stroke:blue"
+            } else {
+                continue;
+            };
+
+            writeln!(mermaid, "    linkStyle {} {}", i + 1, stroke).unwrap();
+        }
+
+        mermaid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handheld::load;
+    use indoc::indoc;
+
+    fn rom() -> Vec<Instruction> {
+        load(
+            0,
+            indoc! {"
+                nop +0
+                acc +1
+                jmp +4
+                acc +3
+                jmp -3
+                acc -99
+                acc +1
+                jmp -4
+                acc +6
+                jmp -20
+            "},
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_builds_basic_blocks() {
+        let rom = rom();
+        let cfg = ControlFlowGraph::new(&rom);
+
+        assert_eq!(
+            vec![
+                (0, 1, Target::Block(1), EdgeKind::Fallthrough),
+                (1, 3, Target::Block(4), EdgeKind::Jump),
+                (3, 5, Target::Block(1), EdgeKind::Jump),
+                (5, 6, Target::Block(4), EdgeKind::Fallthrough),
+                (6, 8, Target::Block(2), EdgeKind::Jump),
+                (8, 10, Target::OutOfBounds(Some(-11)), EdgeKind::Jump),
+            ],
+            cfg.blocks
+                .iter()
+                .map(|block| (block.start, block.end, block.next, block.kind))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(1), cfg.loop_start);
+        assert_eq!(
+            Trace::record(&rom)
+                .loop_report()
+                .map(|report| report.repeated),
+            cfg.loop_start
+        );
+        assert_eq!(
+            vec![5, 8],
+            cfg.unreachable()
+                .map(|block| block.start)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![false, true, true, false, true, false],
+            cfg.blocks
+                .iter()
+                .map(|block| block.looping)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_handles_jumps_past_isize() {
+        let rom = load(0, "nop +0\njmp +9223372036854775807").unwrap();
+        let cfg = ControlFlowGraph::new(&rom);
+
+        assert_eq!(
+            vec![(0, 2, Target::OutOfBounds(None), EdgeKind::Jump)],
+            cfg.blocks
+                .iter()
+                .map(|block| (block.start, block.end, block.next, block.kind))
+                .collect::<Vec<_>>()
+        );
+        assert!(cfg.to_dot().contains("out of bounds: overflow"));
+    }
+
+    #[test]
+    fn it_exports_dot() {
+        let rom = load(0, "acc +1\njmp -1").unwrap();
+        let cfg = ControlFlowGraph::new(&rom);
+
+        assert_eq!(
+            indoc! {r#"
+                digraph program {
+                    node [shape=box, fontname="monospace"];
+                    start [shape=point];
+                    exit [shape=doublecircle, label="end"];
+                    b0 [label="0: acc +1\l1: jmp -1\l", color=red, penwidth=2];
+                    start -> b0 [color=blue];
+                    b0 -> b0 [label="jmp", color=red, penwidth=2];
+                }
+            "#},
+            cfg.to_dot()
+        );
+    }
+
+    #[test]
+    fn it_exports_mermaid() {
+        let rom = load(0, "jmp +2\nacc +1\nacc +2").unwrap();
+        let cfg = ControlFlowGraph::new(&rom);
+
+        assert_eq!(
+            indoc! {r#"
+                flowchart TD
+                    start((start))
+                    exit(((end)))
+                    b0["0: jmp +2"]
+                    b1["1: acc +1"]
+                    b2["2: acc +2"]
+                    start --> b0
+                    b0 -->|jmp| b2
+                    b1 -->|next| b2
+                    b2 -->|next| exit
+                    classDef executed stroke:blue
+                    classDef looping stroke:red,stroke-width:2px
+                    classDef unreachable stroke-dasharray:5 5,color:gray
+                    class b0,b2 executed
+                    class b1 unreachable
+                    linkStyle 1 stroke:blue
+                    linkStyle 3 stroke:blue
+            "#},
+            cfg.to_mermaid()
+        );
+    }
+}
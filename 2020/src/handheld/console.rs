@@ -0,0 +1,282 @@
+use super::{Instruction, Op};
+use std::{convert::TryFrom, fmt};
+
+/// The registers of a console, plus the number of instructions it has run.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct State {
+    pub pc: isize,
+    pub acc: isize,
+    pub steps: usize,
+}
+
+/// A single executed instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub acc_before: isize,
+    pub acc_after: isize,
+}
+
+/// Why a console stopped running.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Halt {
+    /// The program counter moved to just past the last instruction.
+    Terminated,
+    /// The instruction at `pc` was about to run a second time.
+    Loop { pc: usize },
+    /// The program counter left the program anywhere other than its end.
+    OutOfBounds { pc: isize },
+    /// The console ran out of steps.
+    StepBudget { steps: usize },
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::Terminated => write!(f, "terminated"),
+            Halt::Loop { pc } => write!(f, "infinite loop at {}", pc),
+            Halt::OutOfBounds { pc } => write!(f, "out of bounds at {}", pc),
+            Halt::StepBudget { steps } => write!(f, "step budget of {} used up", steps),
+        }
+    }
+}
+
+/// When a console should stop, besides running off the end of its program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Halting {
+    pub on_loop: bool,
+    pub step_budget: Option<usize>,
+}
+
+impl Default for Halting {
+    fn default() -> Self {
+        Halting {
+            on_loop: true,
+            step_budget: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Console {
+    rom: Vec<Instruction>,
+    state: State,
+    halting: Halting,
+    visited: Vec<bool>,
+    trace: Option<Vec<Step>>,
+}
+
+impl Console {
+    pub fn new(rom: Vec<Instruction>) -> Self {
+        Console {
+            visited: vec![false; rom.len()],
+            rom,
+            state: State::default(),
+            halting: Halting::default(),
+            trace: None,
+        }
+    }
+
+    pub fn with_halting(mut self, halting: Halting) -> Self {
+        self.halting = halting;
+        self
+    }
+
+    /// Records every executed instruction, see [`Console::trace`].
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    pub fn rom(&self) -> &[Instruction] {
+        &self.rom
+    }
+
+    pub fn rom_mut(&mut self) -> &mut [Instruction] {
+        &mut self.rom
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn pc(&self) -> isize {
+        self.state.pc
+    }
+
+    pub fn acc(&self) -> isize {
+        self.state.acc
+    }
+
+    /// Every instruction executed so far, if tracing is enabled.
+    pub fn trace(&self) -> Option<&[Step]> {
+        self.trace.as_deref()
+    }
+
+    /// Whether the instruction at `pc` has run since the last reset.
+    pub fn visited(&self, pc: usize) -> bool {
+        self.visited.get(pc).copied().unwrap_or(false)
+    }
+
+    /// Puts the console back to its initial state, keeping the ROM.
+    pub fn reset(&mut self) {
+        self.state = State::default();
+        self.visited = vec![false; self.rom.len()];
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
+
+    /// The reason the console would halt instead of taking its next step.
+    pub fn halt(&self) -> Option<Halt> {
+        let pc = match usize::try_from(self.state.pc) {
+            Ok(pc) if pc < self.rom.len() => pc,
+            Ok(pc) if pc == self.rom.len() => return Some(Halt::Terminated),
+            _ => return Some(Halt::OutOfBounds { pc: self.state.pc }),
+        };
+
+        if self.halting.on_loop && self.visited[pc] {
+            return Some(Halt::Loop { pc });
+        }
+
+        match self.halting.step_budget {
+            Some(budget) if self.state.steps >= budget => Some(Halt::StepBudget {
+                steps: self.state.steps,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Runs a single instruction, or reports why the console has halted.
+    pub fn step(&mut self) -> Result<Step, Halt> {
+        if let Some(halt) = self.halt() {
+            return Err(halt);
+        }
+
+        let pc = self.state.pc as usize;
+        let instruction = self.rom[pc];
+        let acc_before = self.state.acc;
+
+        match instruction.op {
+            Op::Nop => self.state.pc += 1,
+            Op::Acc => {
+                self.state.acc = self.state.acc.wrapping_add(instruction.arg);
+                self.state.pc += 1;
+            }
+            Op::Jmp => self.state.pc = self.state.pc.wrapping_add(instruction.arg),
+        }
+
+        self.state.steps += 1;
+        self.visited[pc] = true;
+
+        let step = Step {
+            pc,
+            instruction,
+            acc_before,
+            acc_after: self.state.acc,
+        };
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(step);
+        }
+
+        Ok(step)
+    }
+
+    /// Runs until the console halts.
+    pub fn run(&mut self) -> Halt {
+        loop {
+            if let Err(halt) = self.step() {
+                return halt;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handheld::load;
+    use indoc::indoc;
+
+    fn rom() -> Vec<Instruction> {
+        load(
+            0,
+            indoc! {"
+                nop +0
+                acc +1
+                jmp +4
+                acc +3
+                jmp -3
+                acc -99
+                acc +1
+                jmp -4
+                acc +6
+            "},
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_halts_on_loops() {
+        let mut console = Console::new(rom());
+
+        assert_eq!(Halt::Loop { pc: 1 }, console.run());
+        assert_eq!(
+            State {
+                pc: 1,
+                acc: 5,
+                steps: 7
+            },
+            console.state()
+        );
+    }
+
+    #[test]
+    fn it_halts_on_step_budgets() {
+        let mut console = Console::new(rom()).with_halting(Halting {
+            on_loop: false,
+            step_budget: Some(20),
+        });
+
+        assert_eq!(Halt::StepBudget { steps: 20 }, console.run());
+        assert!(console.visited(6));
+        assert!(!console.visited(5));
+    }
+
+    #[test]
+    fn it_halts_at_the_end_or_out_of_bounds() {
+        let mut rom = rom();
+        rom[7] = rom[7].toggled().unwrap();
+        let mut console = Console::new(rom);
+
+        assert_eq!(Halt::Terminated, console.run());
+        assert_eq!(8, console.acc());
+
+        let mut console = Console::new(vec![Instruction::new(Op::Jmp, -2)]);
+        assert_eq!(Halt::OutOfBounds { pc: -2 }, console.run());
+    }
+
+    #[test]
+    fn it_traces_execution() {
+        let mut console = Console::new(rom()).with_trace();
+        console.run();
+
+        let trace = console.trace().unwrap();
+        assert_eq!(7, trace.len());
+        assert_eq!(
+            Step {
+                pc: 3,
+                instruction: Instruction::new(Op::Acc, 3),
+                acc_before: 2,
+                acc_after: 5,
+            },
+            trace[5]
+        );
+
+        console.reset();
+        assert_eq!(Some(&[][..]), console.trace());
+        assert_eq!(State::default(), console.state());
+    }
+}
@@ -0,0 +1,598 @@
+use super::{Console, Instruction, Step};
+use std::{
+    convert::TryFrom,
+    fmt,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+
+    fn holds(self, left: isize, right: isize) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "==" => Comparison::Eq,
+            "!=" => Comparison::Ne,
+            "<" => Comparison::Lt,
+            "<=" => Comparison::Le,
+            ">" => Comparison::Gt,
+            ">=" => Comparison::Ge,
+            _ => return Err(format!("unknown comparison `{}`", s)),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Breakpoint {
+    /// Stops before the instruction at this index runs, including the first.
+    Pc(usize),
+    /// Stops after a step that makes the accumulator start matching.
+    Acc(Comparison, isize),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Pc(pc) => write!(f, "pc {}", pc),
+            Breakpoint::Acc(comparison, value) => {
+                write!(f, "acc {} {}", comparison.symbol(), value)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    Step(usize),
+    Continue,
+    Break(Breakpoint),
+    Delete(usize),
+    Breakpoints,
+    Watch,
+    Unwatch,
+    List(usize),
+    Toggle(Option<usize>),
+    Print,
+    Reset,
+    Help,
+    Quit,
+}
+
+const HELP: &str = "\
+step [n]            run n instructions (default 1)
+continue            run until a breakpoint or the console halts
+break pc <n>        stop before instruction n
+break acc <op> <n>  stop when acc compares to n (==, !=, <, <=, >, >=)
+delete <n>          remove breakpoint n (numbers are never reused)
+breakpoints         list breakpoints
+watch / unwatch     print every change to acc
+list [n]            disassemble n instructions either side of pc (default 3)
+toggle [i]          swap nop/jmp at i (default pc)
+print               show pc, acc and steps taken
+reset               restart the program, keeping breakpoints and toggles
+quit";
+
+fn parse_number<T: FromStr>(s: Option<&str>, what: &str) -> Result<T, String> {
+    let s = s.ok_or_else(|| format!("expected {}", what))?;
+    s.parse()
+        .map_err(|_| format!("expected {}, found `{}`", what, s))
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let command = words.next().unwrap_or("");
+
+        let command = match command {
+            "s" | "step" => Command::Step(
+                words
+                    .next()
+                    .map_or(Ok(1), |n| parse_number(Some(n), "a count"))?,
+            ),
+            "c" | "continue" => Command::Continue,
+            "b" | "break" => match words.next() {
+                Some("pc") => {
+                    Command::Break(Breakpoint::Pc(parse_number(words.next(), "an index")?))
+                }
+                Some("acc") => {
+                    let comparison = words
+                        .next()
+                        .ok_or_else(|| String::from("expected a comparison"))?
+                        .parse()?;
+                    let value = parse_number(words.next(), "a value")?;
+
+                    Command::Break(Breakpoint::Acc(comparison, value))
+                }
+                _ => {
+                    return Err(String::from(
+                        "expected `break pc <n>` or `break acc <op> <n>`",
+                    ))
+                }
+            },
+            "d" | "delete" => Command::Delete(parse_number(words.next(), "a breakpoint number")?),
+            "breakpoints" => Command::Breakpoints,
+            "w" | "watch" => Command::Watch,
+            "unwatch" => Command::Unwatch,
+            "l" | "list" => Command::List(
+                words
+                    .next()
+                    .map_or(Ok(3), |n| parse_number(Some(n), "a count"))?,
+            ),
+            "t" | "toggle" => Command::Toggle(
+                words
+                    .next()
+                    .map(|i| parse_number(Some(i), "an index"))
+                    .transpose()?,
+            ),
+            "p" | "print" => Command::Print,
+            "reset" => Command::Reset,
+            "h" | "help" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            "" => return Err(String::from("expected a command, try `help`")),
+            _ => return Err(format!("unknown command `{}`, try `help`", command)),
+        };
+
+        if words.next().is_some() {
+            return Err(String::from("too many arguments"));
+        }
+
+        Ok(command)
+    }
+}
+
+/// Drives a console one command at a time.
+///
+/// The console stops on loops as usual, so `continue` always returns, and
+/// `toggle` edits the ROM in place so a repair can be tried without reloading.
+///
+/// Breakpoints are numbered from 1 in the order they are set, like gdb, and
+/// keep their number when others are deleted.
+pub struct Debugger {
+    console: Console,
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_breakpoint: usize,
+    /// Whether the console is stopped on a `pc` breakpoint, which `continue`
+    /// should run past rather than stopping again.
+    at_breakpoint: bool,
+    watch: bool,
+}
+
+impl Debugger {
+    pub fn new(rom: Vec<Instruction>) -> Self {
+        Debugger {
+            console: Console::new(rom),
+            breakpoints: Vec::new(),
+            next_breakpoint: 1,
+            at_breakpoint: false,
+            watch: false,
+        }
+    }
+
+    pub fn console(&self) -> &Console {
+        &self.console
+    }
+
+    /// The breakpoints that are set, with their numbers.
+    pub fn breakpoints(&self) -> &[(usize, Breakpoint)] {
+        &self.breakpoints
+    }
+
+    /// A `pc` breakpoint on the instruction about to run, if the console is
+    /// going to run it.
+    fn pc_breakpoint(&self) -> Option<(usize, Breakpoint)> {
+        if self.console.halt().is_some() {
+            return None;
+        }
+
+        self.breakpoints
+            .iter()
+            .copied()
+            .find(|(_, breakpoint)| *breakpoint == Breakpoint::Pc(self.console.pc() as usize))
+    }
+
+    /// An `acc` breakpoint that `step` made start matching.
+    fn acc_breakpoint(&self, step: &Step) -> Option<(usize, Breakpoint)> {
+        self.breakpoints
+            .iter()
+            .copied()
+            .find(|(_, breakpoint)| match *breakpoint {
+                Breakpoint::Pc(_) => false,
+                Breakpoint::Acc(comparison, value) => {
+                    comparison.holds(step.acc_after, value)
+                        && !comparison.holds(step.acc_before, value)
+                }
+            })
+    }
+
+    fn print_position(&self, out: &mut impl Write) -> io::Result<()> {
+        match self.console.halt() {
+            Some(halt) => writeln!(out, "halted: {}", halt),
+            None => {
+                let pc = self.console.pc();
+                writeln!(out, "{:>5}: {}", pc, self.console.rom()[pc as usize])
+            }
+        }
+    }
+
+    /// Runs one instruction, reporting acc changes when watching.
+    fn step(&mut self, out: &mut impl Write) -> io::Result<Option<Step>> {
+        match self.console.step() {
+            Ok(step) => {
+                if self.watch && step.acc_before != step.acc_after {
+                    writeln!(
+                        out,
+                        "acc: {} -> {} at {}",
+                        step.acc_before, step.acc_after, step.pc
+                    )?;
+                }
+                Ok(Some(step))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Carries out a command, returning `false` once the session should end.
+    pub fn execute(&mut self, command: Command, out: &mut impl Write) -> io::Result<bool> {
+        match command {
+            Command::Step(n) => {
+                self.at_breakpoint = false;
+                for _ in 0..n {
+                    if self.step(out)?.is_none() {
+                        break;
+                    }
+                }
+                self.print_position(out)?;
+            }
+            Command::Continue => {
+                let mut resuming = std::mem::take(&mut self.at_breakpoint);
+
+                loop {
+                    if !resuming {
+                        if let Some((id, breakpoint)) = self.pc_breakpoint() {
+                            writeln!(out, "breakpoint {}: {}", id, breakpoint)?;
+                            self.at_breakpoint = true;
+                            break;
+                        }
+                    }
+                    resuming = false;
+
+                    let step = match self.step(out)? {
+                        Some(step) => step,
+                        None => break,
+                    };
+                    if let Some((id, breakpoint)) = self.acc_breakpoint(&step) {
+                        writeln!(out, "breakpoint {}: {}", id, breakpoint)?;
+                        break;
+                    }
+                }
+                self.print_position(out)?;
+            }
+            Command::Break(breakpoint) => {
+                let id = self.next_breakpoint;
+                self.next_breakpoint += 1;
+                self.breakpoints.push((id, breakpoint));
+                writeln!(out, "breakpoint {}: {}", id, breakpoint)?;
+            }
+            Command::Delete(id) => match self.breakpoints.iter().position(|&(i, _)| i == id) {
+                Some(i) => {
+                    let (_, breakpoint) = self.breakpoints.remove(i);
+                    writeln!(out, "deleted breakpoint {}: {}", id, breakpoint)?;
+                }
+                None => writeln!(out, "no breakpoint {}", id)?,
+            },
+            Command::Breakpoints => {
+                for (id, breakpoint) in &self.breakpoints {
+                    writeln!(out, "{}: {}", id, breakpoint)?;
+                }
+            }
+            Command::Watch => self.watch = true,
+            Command::Unwatch => self.watch = false,
+            Command::List(n) => {
+                let pc = self.console.pc();
+                let rom = self.console.rom();
+                let n = isize::try_from(n).unwrap_or(isize::MAX);
+                let start = usize::try_from(pc.saturating_sub(n)).unwrap_or(0);
+                let end = usize::try_from(pc.saturating_add(n).saturating_add(1))
+                    .map_or(0, |end| end.min(rom.len()));
+
+                for (i, instruction) in rom.iter().enumerate().take(end).skip(start) {
+                    let marker = if i as isize == pc { "=>" } else { "  " };
+                    let breakpoint = if self
+                        .breakpoints
+                        .iter()
+                        .any(|&(_, breakpoint)| breakpoint == Breakpoint::Pc(i))
+                    {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    writeln!(out, "{}{}{:>4}: {}", marker, breakpoint, i, instruction)?;
+                }
+            }
+            Command::Toggle(i) => {
+                let i = i.or_else(|| usize::try_from(self.console.pc()).ok());
+                let rom = self.console.rom_mut();
+
+                match i.and_then(|i| rom.get_mut(i).map(|instruction| (i, instruction))) {
+                    Some((i, instruction)) => match instruction.toggled() {
+                        Some(toggled) => {
+                            writeln!(out, "{}: {} -> {}", i, instruction, toggled)?;
+                            *instruction = toggled;
+                        }
+                        None => writeln!(out, "{}: cannot toggle {}", i, instruction)?,
+                    },
+                    None => writeln!(out, "no instruction to toggle")?,
+                }
+            }
+            Command::Print => {
+                let state = self.console.state();
+                writeln!(
+                    out,
+                    "pc {}, acc {}, steps {}",
+                    state.pc, state.acc, state.steps
+                )?;
+            }
+            Command::Reset => {
+                self.at_breakpoint = false;
+                self.console.reset();
+                self.print_position(out)?;
+            }
+            Command::Help => writeln!(out, "{}", HELP)?,
+            Command::Quit => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Reads commands line by line until `quit` or the end of the input.
+    ///
+    /// Blank lines and lines starting with `#` are skipped so scripts can be
+    /// commented. With `prompt` set, each command is prompted for.
+    pub fn repl(
+        &mut self,
+        input: impl BufRead,
+        mut out: impl Write,
+        prompt: bool,
+    ) -> io::Result<()> {
+        if prompt {
+            self.print_position(&mut out)?;
+        }
+
+        let mut lines = input.lines();
+        loop {
+            if prompt {
+                write!(out, "(handheld) ")?;
+                out.flush()?;
+            }
+
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => return Ok(()),
+            };
+
+            let line = line.trim();
+            if !prompt && (line.is_empty() || line.starts_with('#')) {
+                continue;
+            }
+
+            match line.parse() {
+                Ok(command) => {
+                    if !self.execute(command, &mut out)? {
+                        return Ok(());
+                    }
+                }
+                Err(message) => writeln!(out, "error: {}", message)?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handheld::load;
+    use indoc::indoc;
+
+    fn debug(script: &str) -> String {
+        let rom = load(
+            0,
+            indoc! {"
+                nop +0
+                acc +1
+                jmp +4
+                acc +3
+                jmp -3
+                acc -99
+                acc +1
+                jmp -4
+                acc +6
+            "},
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        Debugger::new(rom)
+            .repl(script.as_bytes(), &mut out, false)
+            .unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn it_parses_commands() {
+        assert_eq!(Ok(Command::Step(1)), "s".parse());
+        assert_eq!(Ok(Command::Step(4)), "step 4".parse());
+        assert_eq!(
+            Ok(Command::Break(Breakpoint::Acc(Comparison::Ge, -3))),
+            "break acc >= -3".parse()
+        );
+        assert_eq!(Ok(Command::Toggle(None)), "t".parse());
+        assert_eq!(
+            Err(String::from("expected an index, found `x`")),
+            "break pc x".parse::<Command>()
+        );
+        assert_eq!(
+            Err(String::from("too many arguments")),
+            "continue now".parse::<Command>()
+        );
+    }
+
+    #[test]
+    fn it_steps_and_breaks() {
+        assert_eq!(
+            indoc! {"
+                    1: acc +1
+                breakpoint 1: pc 7
+                breakpoint 2: acc > 2
+                breakpoint 1: pc 7
+                    7: jmp -4
+                deleted breakpoint 1: pc 7
+                no breakpoint 1
+                2: acc > 2
+                breakpoint 2: acc > 2
+                    4: jmp -3
+                pc 4, acc 5, steps 6
+                halted: infinite loop at 1
+            "},
+            debug(indoc! {"
+                # run up to the broken jump
+                step
+                break pc 7
+                break acc > 2
+                continue
+                delete 1
+                delete 1
+                breakpoints
+
+                continue
+                print
+                continue
+            "})
+        );
+    }
+
+    #[test]
+    fn it_breaks_before_the_first_instruction() {
+        assert_eq!(
+            indoc! {"
+                breakpoint 1: pc 0
+                breakpoint 2: pc 1
+                breakpoint 1: pc 0
+                    0: nop +0
+                breakpoint 2: pc 1
+                    1: acc +1
+                halted: infinite loop at 1
+                    0: nop +0
+                breakpoint 1: pc 0
+                    0: nop +0
+            "},
+            debug(indoc! {"
+                break pc 0
+                break pc 1
+                continue
+                continue
+                continue
+                reset
+                continue
+            "})
+        );
+    }
+
+    #[test]
+    fn it_watches_and_lists() {
+        assert_eq!(
+            indoc! {"
+                acc: 0 -> 1 at 1
+                    2: jmp +4
+                      1: acc +1
+                =>    2: jmp +4
+                      3: acc +3
+            "},
+            debug(indoc! {"
+                watch
+                step 2
+                list 1
+            "})
+        );
+    }
+
+    #[test]
+    fn it_lists_everything_for_a_huge_count() {
+        assert_eq!(
+            indoc! {"
+                    1: acc +1
+                      0: nop +0
+                =>    1: acc +1
+                      2: jmp +4
+                      3: acc +3
+                      4: jmp -3
+                      5: acc -99
+                      6: acc +1
+                      7: jmp -4
+                      8: acc +6
+            "},
+            debug(indoc! {"
+                step
+                list 9223372036854775807
+            "})
+        );
+    }
+
+    #[test]
+    fn it_tries_repairs() {
+        assert_eq!(
+            indoc! {"
+                7: jmp -4 -> nop -4
+                8: cannot toggle acc +6
+                halted: terminated
+                pc 9, acc 8, steps 6
+                error: unknown command `jump`, try `help`
+            "},
+            debug(indoc! {"
+                toggle 7
+                toggle 8
+                continue
+                print
+                jump
+                quit
+                print
+            "})
+        );
+    }
+}
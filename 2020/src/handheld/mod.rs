@@ -0,0 +1,192 @@
+mod assembler;
+mod cfg;
+mod console;
+mod debugger;
+mod repair;
+mod trace;
+
+pub use assembler::{assemble, disassemble};
+pub use cfg::{Block, ControlFlowGraph, EdgeKind, Target};
+pub use console::{Console, Halt, Halting, State, Step};
+pub use debugger::{Breakpoint, Command, Comparison, Debugger};
+pub use repair::{repair, terminating, Repair};
+pub use trace::{LoopReport, Trace};
+
+use crate::error::ParseError;
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Op {
+    Nop,
+    Acc,
+    Jmp,
+}
+
+impl Op {
+    pub const ALL: [Op; 3] = [Op::Nop, Op::Acc, Op::Jmp];
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Op::Nop => "nop",
+            Op::Acc => "acc",
+            Op::Jmp => "jmp",
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic())
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Op::ALL
+            .iter()
+            .copied()
+            .find(|op| op.mnemonic() == s)
+            .ok_or_else(|| format!("unknown operation `{}`", s))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Instruction {
+    pub op: Op,
+    pub arg: isize,
+}
+
+impl Instruction {
+    pub fn new(op: Op, arg: isize) -> Self {
+        Instruction { op, arg }
+    }
+
+    /// The instruction with `nop` and `jmp` swapped, if it is either.
+    pub fn toggled(self) -> Option<Self> {
+        let op = match self.op {
+            Op::Nop => Op::Jmp,
+            Op::Jmp => Op::Nop,
+            Op::Acc => return None,
+        };
+
+        Some(Instruction { op, ..self })
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:+}", self.op, self.arg)
+    }
+}
+
+fn parse_instruction(day: u32, source: &str, line: &str) -> Result<Instruction, ParseError> {
+    let (op, arg) = line.split_once(' ').ok_or_else(|| {
+        ParseError::at(day, source, line, "expected an instruction like `acc +1`")
+    })?;
+
+    let op = op
+        .parse()
+        .map_err(|message: String| ParseError::at(day, source, op, message))?;
+
+    let arg = match arg.as_bytes().first() {
+        Some(b'+') | Some(b'-') => arg
+            .parse()
+            .map_err(|e| ParseError::at(day, source, arg, format!("invalid argument: {}", e)))?,
+        _ => {
+            return Err(ParseError::at(
+                day,
+                source,
+                arg,
+                "expected a signed argument like `+1`",
+            ))
+        }
+    };
+
+    Ok(Instruction { op, arg })
+}
+
+/// Loads a program with one instruction per line.
+pub fn load(day: u32, source: &str) -> Result<Vec<Instruction>, ParseError> {
+    source
+        .lines()
+        .map(|line| parse_instruction(day, source, line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn it_loads_programs() {
+        let rom = load(
+            8,
+            indoc! {"
+                nop +0
+                acc -99
+                jmp +4
+            "},
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Instruction::new(Op::Nop, 0),
+                Instruction::new(Op::Acc, -99),
+                Instruction::new(Op::Jmp, 4),
+            ],
+            rom
+        );
+        assert_eq!(
+            "nop +0, acc -99, jmp +4",
+            rom.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_programs() {
+        assert_eq!(
+            Err(ParseError::new(0, 2, 1, "mov", "unknown operation `mov`")),
+            load(0, "nop +0\nmov +1")
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                0,
+                1,
+                5,
+                "1",
+                "expected a signed argument like `+1`"
+            )),
+            load(0, "acc 1")
+        );
+        assert_eq!(
+            Err(ParseError::new(
+                0,
+                1,
+                1,
+                "acc",
+                "expected an instruction like `acc +1`"
+            )),
+            load(0, "acc")
+        );
+    }
+
+    #[test]
+    fn it_toggles_instructions() {
+        assert_eq!(
+            Some(Instruction::new(Op::Jmp, -3)),
+            Instruction::new(Op::Nop, -3).toggled()
+        );
+        assert_eq!(
+            Some(Instruction::new(Op::Nop, 2)),
+            Instruction::new(Op::Jmp, 2).toggled()
+        );
+        assert_eq!(None, Instruction::new(Op::Acc, 1).toggled());
+    }
+}
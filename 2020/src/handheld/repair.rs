@@ -0,0 +1,156 @@
+use super::{Console, Halt, Instruction, Op};
+use std::{collections::VecDeque, convert::TryFrom, fmt};
+
+/// A single `nop`/`jmp` swap that makes a program terminate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Repair {
+    pub index: usize,
+    pub from: Instruction,
+    pub to: Instruction,
+}
+
+impl Repair {
+    pub fn apply(&self, rom: &mut [Instruction]) {
+        rom[self.index] = self.to;
+    }
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.index, self.from, self.to)
+    }
+}
+
+/// Where control goes after the instruction at `pc`, if that is still inside
+/// the program or just past its end.
+fn successor(rom: &[Instruction], pc: usize, instruction: Instruction) -> Option<usize> {
+    let next = match instruction.op {
+        Op::Nop | Op::Acc => pc.checked_add(1),
+        Op::Jmp => isize::try_from(pc)
+            .ok()
+            .and_then(|pc| pc.checked_add(instruction.arg))
+            .and_then(|pc| usize::try_from(pc).ok()),
+    };
+
+    next.filter(|&next| next <= rom.len())
+}
+
+/// For every instruction, whether running the program from it terminates.
+///
+/// Works backwards from the end of the program over the control-flow graph,
+/// so it takes linear time.
+pub fn terminating(rom: &[Instruction]) -> Vec<bool> {
+    let mut predecessors = vec![Vec::new(); rom.len() + 1];
+    for (pc, &instruction) in rom.iter().enumerate() {
+        if let Some(next) = successor(rom, pc, instruction) {
+            predecessors[next].push(pc);
+        }
+    }
+
+    let mut terminating = vec![false; rom.len() + 1];
+    terminating[rom.len()] = true;
+
+    let mut queue = VecDeque::from(vec![rom.len()]);
+    while let Some(pc) = queue.pop_front() {
+        for &previous in &predecessors[pc] {
+            if !terminating[previous] {
+                terminating[previous] = true;
+                queue.push_back(previous);
+            }
+        }
+    }
+
+    terminating
+}
+
+/// Finds the swap that makes a looping program terminate.
+///
+/// Only instructions the broken program actually runs can change its outcome,
+/// and swapping one of them fixes the program exactly when the swapped
+/// instruction leads somewhere that already terminates. Returns `None` if the
+/// program terminates as it is or no single swap fixes it.
+pub fn repair(rom: &[Instruction]) -> Option<Repair> {
+    let mut console = Console::new(rom.to_vec()).with_trace();
+    if console.run() == Halt::Terminated {
+        return None;
+    }
+
+    let terminating = terminating(rom);
+
+    console.trace()?.iter().find_map(|step| {
+        let to = step.instruction.toggled()?;
+
+        successor(rom, step.pc, to)
+            .filter(|&next| terminating[next])
+            .map(|_| Repair {
+                index: step.pc,
+                from: step.instruction,
+                to,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handheld::load;
+    use indoc::indoc;
+
+    #[test]
+    fn it_finds_terminating_instructions() {
+        let rom = load(
+            0,
+            indoc! {"
+                nop +0
+                acc +1
+                jmp +4
+                acc +3
+                jmp -3
+                acc -99
+                acc +1
+                jmp -4
+                acc +6
+            "},
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![false, false, false, false, false, false, false, false, true, true],
+            terminating(&rom)
+        );
+        assert_eq!(
+            Some(Repair {
+                index: 7,
+                from: Instruction::new(Op::Jmp, -4),
+                to: Instruction::new(Op::Nop, -4),
+            }),
+            repair(&rom)
+        );
+        assert_eq!("7: jmp -4 -> nop -4", repair(&rom).unwrap().to_string());
+    }
+
+    #[test]
+    fn it_repairs_large_programs() {
+        let len = 200_000;
+        let mut rom: Vec<_> = (0..len)
+            .map(|i| match i % 2 {
+                0 => Instruction::new(Op::Nop, 0),
+                _ => Instruction::new(Op::Acc, 1),
+            })
+            .collect();
+        rom[len - 1] = Instruction::new(Op::Jmp, -(len as isize - 1));
+
+        let repair = repair(&rom).unwrap();
+        assert_eq!(len - 1, repair.index);
+
+        repair.apply(&mut rom);
+        let mut console = Console::new(rom);
+        assert_eq!(Halt::Terminated, console.run());
+        assert_eq!(len as isize / 2 - 1, console.acc());
+    }
+
+    #[test]
+    fn it_leaves_terminating_programs_alone() {
+        assert_eq!(None, repair(&[Instruction::new(Op::Acc, 1)]));
+    }
+}
@@ -0,0 +1,228 @@
+use super::{Console, Halt, Instruction, State, Step};
+use serde::Serialize;
+use std::fmt::{self, Write};
+
+#[derive(Serialize)]
+struct Record {
+    pc: usize,
+    op: &'static str,
+    arg: isize,
+    acc_before: isize,
+    acc_after: isize,
+}
+
+impl From<&Step> for Record {
+    fn from(step: &Step) -> Self {
+        Record {
+            pc: step.pc,
+            op: step.instruction.op.mnemonic(),
+            arg: step.instruction.arg,
+            acc_before: step.acc_before,
+            acc_after: step.acc_after,
+        }
+    }
+}
+
+/// Every instruction a program ran, and why it stopped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub halt: Halt,
+    pub end: State,
+}
+
+impl Trace {
+    /// Runs a program from the start until it halts, recording each step.
+    pub fn record(rom: &[Instruction]) -> Self {
+        let mut console = Console::new(rom.to_vec()).with_trace();
+        let halt = console.run();
+
+        Trace {
+            steps: console.trace().unwrap_or_default().to_vec(),
+            halt,
+            end: console.state(),
+        }
+    }
+
+    /// The console's state after each recorded step, in order.
+    pub fn replay(&self) -> impl Iterator<Item = State> + '_ {
+        self.steps.iter().enumerate().map(move |(i, step)| State {
+            pc: self
+                .steps
+                .get(i + 1)
+                .map_or(self.end.pc, |next| next.pc as isize),
+            acc: step.acc_after,
+            steps: i + 1,
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("pc,op,arg,acc_before,acc_after\n");
+
+        for step in &self.steps {
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                step.pc, step.instruction.op, step.instruction.arg, step.acc_before, step.acc_after
+            )
+            .unwrap();
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.steps.iter().map(Record::from).collect::<Vec<_>>())
+    }
+
+    /// How the program ended up in its infinite loop, if it has one.
+    pub fn loop_report(&self) -> Option<LoopReport<'_>> {
+        let repeated = match self.halt {
+            Halt::Loop { pc } => pc,
+            _ => return None,
+        };
+
+        let start = self.steps.iter().position(|step| step.pc == repeated)?;
+
+        Some(LoopReport {
+            path: &self.steps[..start],
+            cycle: &self.steps[start..],
+            repeated,
+        })
+    }
+}
+
+/// The steps leading into an infinite loop, then the loop itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LoopReport<'a> {
+    pub path: &'a [Step],
+    pub cycle: &'a [Step],
+    /// The first instruction that was about to run a second time.
+    pub repeated: usize,
+}
+
+impl LoopReport<'_> {
+    /// The accumulator just before the repeated instruction would run again.
+    pub fn acc(&self) -> isize {
+        self.cycle.last().map_or(0, |step| step.acc_after)
+    }
+}
+
+impl fmt::Display for LoopReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = |f: &mut fmt::Formatter<'_>, step: &Step| {
+            writeln!(
+                f,
+                "  {:>5}: {:<8} acc {} -> {}",
+                step.pc,
+                step.instruction.to_string(),
+                step.acc_before,
+                step.acc_after
+            )
+        };
+
+        writeln!(f, "path into the loop ({} steps):", self.path.len())?;
+        for step in self.path {
+            line(f, step)?;
+        }
+
+        writeln!(f, "loop ({} steps):", self.cycle.len())?;
+        for step in self.cycle {
+            line(f, step)?;
+        }
+
+        writeln!(
+            f,
+            "instruction {} runs again with acc {}",
+            self.repeated,
+            self.acc()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handheld::load;
+    use indoc::indoc;
+
+    fn trace() -> Trace {
+        Trace::record(
+            &load(
+                0,
+                indoc! {"
+                    nop +0
+                    acc +1
+                    jmp +4
+                    acc +3
+                    jmp -3
+                    acc -99
+                    acc +1
+                    jmp -4
+                    acc +6
+                "},
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn it_exports_traces() {
+        let trace = trace();
+
+        assert_eq!(Halt::Loop { pc: 1 }, trace.halt);
+        assert_eq!(
+            indoc! {"
+                pc,op,arg,acc_before,acc_after
+                0,nop,0,0,0
+                1,acc,1,0,1
+                2,jmp,4,1,1
+                6,acc,1,1,2
+                7,jmp,-4,2,2
+                3,acc,3,2,5
+                4,jmp,-3,5,5
+            "},
+            trace.to_csv()
+        );
+        assert!(trace.to_json().unwrap().starts_with(
+            r#"[{"pc":0,"op":"nop","arg":0,"acc_before":0,"acc_after":0},{"pc":1,"op":"acc","#
+        ));
+
+        assert_eq!(Some(trace.end), trace.replay().last());
+        assert_eq!(
+            Some(State {
+                pc: 6,
+                acc: 1,
+                steps: 3
+            }),
+            trace.replay().nth(2)
+        );
+    }
+
+    #[test]
+    fn it_reports_loops() {
+        let trace = trace();
+        let report = trace.loop_report().unwrap();
+
+        assert_eq!(1, report.repeated);
+        assert_eq!(5, report.acc());
+        assert_eq!(
+            indoc! {"
+                path into the loop (1 steps):
+                      0: nop +0   acc 0 -> 0
+                loop (6 steps):
+                      1: acc +1   acc 0 -> 1
+                      2: jmp +4   acc 1 -> 1
+                      6: acc +1   acc 1 -> 2
+                      7: jmp -4   acc 2 -> 2
+                      3: acc +3   acc 2 -> 5
+                      4: jmp -3   acc 5 -> 5
+                instruction 1 runs again with acc 5
+            "},
+            report.to_string()
+        );
+
+        let terminating = Trace::record(&load(0, "acc +1").unwrap());
+        assert_eq!(None, terminating.loop_report());
+    }
+}
@@ -0,0 +1,31 @@
+/// Splits the input into blank-line separated paragraphs, along with the
+/// number of lines that come before each one.
+pub fn paragraphs(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.split("\n\n").map(move |paragraph| {
+        let offset = paragraph.as_ptr() as usize - input.as_ptr() as usize;
+        (input[..offset].matches('\n').count(), paragraph)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn it_splits_paragraphs() {
+        let data = indoc! {"
+            abc
+
+            a
+            b
+
+            c
+        "};
+
+        assert_eq!(
+            vec![(0, "abc"), (2, "a\nb"), (5, "c\n")],
+            paragraphs(data).collect::<Vec<_>>()
+        );
+    }
+}
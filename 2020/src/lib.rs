@@ -1,6 +1,10 @@
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod answer;
+pub mod answers;
+pub mod automaton;
+pub mod context;
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -12,5 +16,10 @@ pub mod day08;
 pub mod day09;
 pub mod day10;
 pub mod day11;
+pub mod error;
+pub mod grid;
+pub mod handheld;
+pub mod input;
+pub mod runner;
 
 aoc_lib! { year = 2020 }
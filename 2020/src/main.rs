@@ -1,3 +1,712 @@
-use aoc_runner_derive::aoc_main;
+use advent_of_code_2020::{
+    answer::Answer,
+    answers::{Answers, Status},
+    day01,
+    day02::{self, PolicyRule},
+    day03::{self, Slope, Stepping},
+    day07::{self, BagGraph, Colour, Subgraph},
+    grid::Grid,
+    handheld::{self, ControlFlowGraph, Debugger, Instruction, Trace},
+    runner::{Day, Outcome, Record, DAYS},
+};
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, BufReader},
+    path::PathBuf,
+    process,
+};
 
-aoc_main! { lib = advent_of_code_2020 }
+#[derive(Parser)]
+#[command(about = "Advent of Code 2020")]
+struct Cli {
+    /// Directory containing the puzzle inputs (`day1.txt`, `day2.txt`, ...)
+    #[arg(long, global = true, default_value = "input/2020")]
+    input: PathBuf,
+
+    /// Print one JSON record per day and part instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run every solution and print its answer
+    Run,
+    /// Check every solution against the known answers for the inputs
+    Verify {
+        /// Answers file, defaults to `answers.json` in the input directory
+        #[arg(long)]
+        answers: Option<PathBuf>,
+        /// Record the answers of solutions that have none yet
+        #[arg(long)]
+        record: bool,
+    },
+    /// Find the day 1 expense report entries that add up to a total
+    Expenses {
+        /// Report to search, defaults to `day1.txt` in the input directory
+        report: Option<PathBuf>,
+        /// How many entries to add up
+        #[arg(long, default_value_t = 2)]
+        count: usize,
+        #[arg(long, default_value_t = 2020)]
+        target: i64,
+        /// List every combination that adds up, not just the first found
+        #[arg(long)]
+        all: bool,
+    },
+    /// Count the day 2 passwords that meet their policy under different rules
+    Passwords {
+        /// Database to check, defaults to `day2.txt` in the input directory
+        database: Option<PathBuf>,
+        /// How to read each policy: corporate, authentication, exactly:N,
+        /// at-most:N, forbidden[:LETTER], letters:SET or regex:PATTERN
+        #[arg(long = "policy", default_values = ["corporate", "authentication"])]
+        policies: Vec<String>,
+        /// List every entry with the reason it passes or fails each policy
+        #[arg(long, value_enum)]
+        report: Option<ReportFormat>,
+        /// Only list the entries that fail, with `--report`
+        #[arg(long, requires = "report")]
+        failures: bool,
+    },
+    /// Count the trees on each of a range of day 3 slopes, and find the best
+    Slopes {
+        /// Map to ride down, defaults to `day3.txt` in the input directory
+        map: Option<PathBuf>,
+        /// Slopes to try, like `3/1` or `1/2`, instead of a sweep
+        #[arg(long = "slope")]
+        slopes: Vec<Slope>,
+        /// Sweep every slope from 1 up to this many squares right
+        #[arg(long, default_value_t = 7)]
+        max_right: usize,
+        /// Sweep every slope up to this many squares down
+        #[arg(long, default_value_t = 2)]
+        max_down: usize,
+        /// Follow each slope through every row, not just the squares it
+        /// lands on
+        #[arg(long)]
+        line: bool,
+    },
+    /// Draw the path down the day 3 map, marking the squares it passes
+    Ride {
+        /// Map to ride down, defaults to `day3.txt` in the input directory
+        map: Option<PathBuf>,
+        #[arg(long, default_value = "3/1")]
+        slope: Slope,
+        /// Follow the slope through every row, not just the squares it lands on
+        #[arg(long)]
+        line: bool,
+        /// Print an SVG image instead
+        #[arg(long)]
+        svg: bool,
+    },
+    /// Print the control-flow graph of a handheld program as Graphviz DOT
+    Cfg {
+        /// Program to graph, defaults to `day8.txt` in the input directory
+        program: Option<PathBuf>,
+        /// Print a Mermaid flowchart instead
+        #[arg(long)]
+        mermaid: bool,
+    },
+    /// Step through a handheld program, reading debugger commands from stdin
+    Debug {
+        /// Program to debug, defaults to `day8.txt` in the input directory
+        program: Option<PathBuf>,
+        /// Read commands from a file instead of stdin
+        #[arg(long)]
+        script: Option<PathBuf>,
+    },
+    /// Record every instruction a handheld program runs
+    Trace {
+        /// Program to trace, defaults to `day8.txt` in the input directory
+        program: Option<PathBuf>,
+        #[arg(long, value_enum, default_value = "report")]
+        format: TraceFormat,
+    },
+    /// Print the day 7 bag rules as a Graphviz DOT graph
+    BagGraph {
+        /// Rules to draw, defaults to `day7.txt` in the input directory
+        rules: Option<PathBuf>,
+        /// Only draw this colour and the bags it eventually holds
+        #[arg(long, conflicts_with = "to")]
+        from: Option<String>,
+        /// Only draw this colour and the bags that eventually hold it
+        #[arg(long)]
+        to: Option<String>,
+        /// Print a Mermaid flowchart instead
+        #[arg(long)]
+        mermaid: bool,
+    },
+    /// Answer questions about any colour in the day 7 bag rules
+    Bags {
+        /// Rules to query, defaults to `day7.txt` in the input directory
+        rules: Option<PathBuf>,
+        #[command(subcommand)]
+        query: BagQuery,
+    },
+}
+
+#[derive(Subcommand)]
+enum BagQuery {
+    /// Every colour that can eventually hold a bag of this colour
+    Containers { colour: String },
+    /// How many bags a bag of this colour holds in total
+    Count { colour: String },
+    /// The chain of bags from one colour down to another
+    Chain {
+        from: String,
+        to: String,
+        /// Find the longest chain rather than the shortest
+        #[arg(long)]
+        longest: bool,
+    },
+    /// How many levels of bags a bag of this colour holds
+    Depth { colour: String },
+    /// How many bags of each colour a bag of this colour holds once unpacked
+    Bom { colour: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TraceFormat {
+    /// How the program gets into its infinite loop
+    Report,
+    Csv,
+    Json,
+}
+
+#[derive(Serialize)]
+struct Verification<'a> {
+    #[serde(flatten)]
+    record: Record<'a>,
+    expected: Option<&'a Answer>,
+    status: Status,
+}
+
+const NO_INPUT: &str = "no input found";
+
+fn run_all(cli: &Cli) -> impl Iterator<Item = (&'static Day, Option<Vec<Outcome>>)> + '_ {
+    DAYS.iter().map(move |day| {
+        let path = cli.input.join(format!("day{}.txt", day.day));
+        let outcomes = fs::read_to_string(path).ok().map(|input| day.run(&input));
+
+        (day, outcomes)
+    })
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    if !cli.json {
+        println!("Advent of code 2020");
+    }
+
+    for (day, outcomes) in run_all(cli) {
+        let outcomes = match outcomes {
+            Some(outcomes) => outcomes,
+            None if cli.json => {
+                print_json(&Record::error(day.day, NO_INPUT))?;
+                continue;
+            }
+            None => {
+                eprintln!("Day {}: {}\n", day.day, NO_INPUT);
+                continue;
+            }
+        };
+
+        for outcome in outcomes {
+            if cli.json {
+                print_json(&Record::from(&outcome))?;
+            } else if outcome.answer.is_failure() {
+                eprintln!(
+                    "Day {} - Part {}: {}\n",
+                    outcome.day, outcome.part, outcome.answer
+                );
+            } else {
+                println!(
+                    "Day {} - Part {}: {}\n\tgenerator: {:?},\n\trunner: {:?}\n",
+                    outcome.day, outcome.part, outcome.answer, outcome.generator, outcome.solver
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_table(verifications: &[Verification]) {
+    let rows: Vec<_> = verifications
+        .iter()
+        .map(|v| {
+            let status = match v.status {
+                Status::Pass => "pass",
+                Status::Fail => "FAIL",
+                Status::Missing => "missing",
+            };
+            let actual = match (v.record.answer, v.record.error) {
+                (Some(answer), _) => answer.to_string(),
+                (None, Some(error)) => error.to_owned(),
+                (None, None) => String::from("-"),
+            };
+
+            (
+                v.record.day.to_string(),
+                v.record
+                    .part
+                    .map_or_else(|| String::from("-"), |p| p.to_string()),
+                status,
+                v.expected
+                    .map_or_else(|| String::from("-"), ToString::to_string),
+                actual,
+            )
+        })
+        .collect();
+
+    let expected_width = rows.iter().map(|row| row.3.len()).max().unwrap_or(0);
+    let expected_width = expected_width.max("Expected".len());
+
+    println!(
+        "{:<3}  {:<4}  {:<7}  {:<width$}  Actual",
+        "Day",
+        "Part",
+        "Status",
+        "Expected",
+        width = expected_width
+    );
+
+    for (day, part, status, expected, actual) in &rows {
+        println!(
+            "{:<3}  {:<4}  {:<7}  {:<width$}  {}",
+            day,
+            part,
+            status,
+            expected,
+            actual,
+            width = expected_width
+        );
+    }
+
+    let count = |status| verifications.iter().filter(|v| v.status == status).count();
+    println!(
+        "\n{} passed, {} failed, {} missing",
+        count(Status::Pass),
+        count(Status::Fail),
+        count(Status::Missing)
+    );
+}
+
+fn verify(cli: &Cli, answers: Option<&PathBuf>, record: bool) -> Result<bool> {
+    let path = answers
+        .cloned()
+        .unwrap_or_else(|| cli.input.join("answers.json"));
+    let mut answers = Answers::load(&path)?;
+
+    let results: Vec<_> = run_all(cli).collect();
+
+    if record {
+        for outcome in results
+            .iter()
+            .flat_map(|(_, outcomes)| outcomes.iter().flatten())
+        {
+            if answers.check(outcome) == Status::Missing && !outcome.answer.is_failure() {
+                answers.insert(outcome.day, outcome.part, outcome.answer.clone());
+            }
+        }
+
+        answers.save(&path)?;
+    }
+
+    let verifications: Vec<_> = results
+        .iter()
+        .flat_map(|(day, outcomes)| match outcomes {
+            Some(outcomes) => outcomes
+                .iter()
+                .map(|outcome| Verification {
+                    record: Record::from(outcome),
+                    expected: answers.get(outcome.day, outcome.part),
+                    status: answers.check(outcome),
+                })
+                .collect(),
+            None => vec![Verification {
+                record: Record::error(day.day, NO_INPUT),
+                expected: None,
+                status: Status::Missing,
+            }],
+        })
+        .collect();
+
+    if cli.json {
+        for verification in &verifications {
+            print_json(verification)?;
+        }
+    } else {
+        print_table(&verifications);
+    }
+
+    Ok(verifications.iter().all(|v| v.status != Status::Fail))
+}
+
+fn expenses(
+    cli: &Cli,
+    report: Option<&PathBuf>,
+    count: usize,
+    target: i64,
+    all: bool,
+) -> Result<()> {
+    let path = report
+        .cloned()
+        .unwrap_or_else(|| cli.input.join("day1.txt"));
+    let entries = day01::generator(fs::read_to_string(path)?.trim_end_matches('\n'))?;
+
+    let found = if all {
+        day01::k_sum_all(&entries, count, target)
+    } else {
+        day01::k_sum(&entries, count, target).into_iter().collect()
+    };
+
+    if found.is_empty() && !cli.json {
+        println!("no {} entries add up to {}", count, target);
+    }
+
+    for entries in found {
+        if cli.json {
+            print_json(&entries)?;
+        } else {
+            println!("{}", day01::describe(&entries));
+        }
+    }
+
+    Ok(())
+}
+
+fn passwords(
+    cli: &Cli,
+    database: Option<&PathBuf>,
+    policies: &[String],
+    report: Option<ReportFormat>,
+    failures: bool,
+) -> Result<()> {
+    let rules = policies
+        .iter()
+        .map(|name| day02::rule(name).map_err(|e| anyhow!(e)))
+        .collect::<Result<Vec<Box<dyn PolicyRule>>>>()?;
+
+    let rules: Vec<_> = rules.iter().map(AsRef::as_ref).collect();
+    let path = database
+        .cloned()
+        .unwrap_or_else(|| cli.input.join("day2.txt"));
+
+    if let Some(format) = report {
+        let entries = day02::load(fs::read_to_string(path)?.trim_end_matches('\n'))?;
+        if rules.iter().any(|rule| rule.uses_positions()) {
+            day02::check_positions(&entries)?;
+        }
+
+        let mut rows = day02::report(&entries, &rules);
+        if failures {
+            rows.retain(|row| !row.verdict.valid);
+        }
+
+        match format {
+            ReportFormat::Csv => print!("{}", day02::to_csv(&rows)),
+            ReportFormat::Text => {
+                for row in rows {
+                    println!("{}", row);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    let database = BufReader::new(fs::File::open(path)?);
+    let (entries, counts) = day02::count_valid_streaming(database, &rules)?;
+
+    for (rule, count) in rules.iter().zip(counts) {
+        println!("{}: {} of {} valid", rule, count, entries);
+    }
+
+    Ok(())
+}
+
+fn load_map(cli: &Cli, map: Option<&PathBuf>) -> Result<Grid<day03::Tile>> {
+    let path = map.cloned().unwrap_or_else(|| cli.input.join("day3.txt"));
+
+    Ok(day03::load(
+        fs::read_to_string(path)?.trim_end_matches('\n'),
+    )?)
+}
+
+fn stepping(line: bool) -> Stepping {
+    if line {
+        Stepping::Line
+    } else {
+        Stepping::Jump
+    }
+}
+
+fn ride(cli: &Cli, map: Option<&PathBuf>, slope: Slope, line: bool, svg: bool) -> Result<()> {
+    let grid = load_map(cli, map)?;
+
+    if svg {
+        print!("{}", grid.render_svg(slope, stepping(line))?);
+    } else {
+        print!("{}", grid.render(slope, stepping(line))?);
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SlopeRecord {
+    slope: String,
+    trees: usize,
+}
+
+fn slopes(
+    cli: &Cli,
+    map: Option<&PathBuf>,
+    slopes: &[Slope],
+    max_right: usize,
+    max_down: usize,
+    line: bool,
+) -> Result<()> {
+    let grid = load_map(cli, map)?;
+    let stepping = stepping(line);
+
+    let table = if slopes.is_empty() {
+        // Following the line, 2/2 is the same as 1/1, so only try each
+        // direction once.
+        let slopes = Slope::range(1..=max_right, 1..=max_down)
+            .filter(|slope| !line || *slope == slope.reduced());
+        grid.sweep(slopes, stepping)
+    } else {
+        grid.sweep(slopes.iter().copied(), stepping)
+    };
+
+    if cli.json {
+        for &(slope, trees) in &table {
+            print_json(&SlopeRecord {
+                slope: slope.to_string(),
+                trees,
+            })?;
+        }
+
+        return Ok(());
+    }
+
+    println!("{:<7}  Trees", "Slope");
+    for (slope, trees) in &table {
+        println!("{:<7}  {}", slope.to_string(), trees);
+    }
+
+    if let Some((slope, trees)) = day03::fewest_trees(&table) {
+        println!("\nfewest trees: {} on {}", trees, slope);
+    }
+
+    Ok(())
+}
+
+fn load_program(cli: &Cli, program: Option<&PathBuf>) -> Result<Vec<Instruction>> {
+    let path = program
+        .cloned()
+        .unwrap_or_else(|| cli.input.join("day8.txt"));
+
+    Ok(handheld::assemble(
+        8,
+        fs::read_to_string(path)?.trim_end_matches('\n'),
+    )?)
+}
+
+fn cfg(cli: &Cli, program: Option<&PathBuf>, mermaid: bool) -> Result<()> {
+    let rom = load_program(cli, program)?;
+    let cfg = ControlFlowGraph::new(&rom);
+
+    if mermaid {
+        print!("{}", cfg.to_mermaid());
+    } else {
+        print!("{}", cfg.to_dot());
+    }
+
+    Ok(())
+}
+
+fn debug(cli: &Cli, program: Option<&PathBuf>, script: Option<&PathBuf>) -> Result<()> {
+    let mut debugger = Debugger::new(load_program(cli, program)?);
+
+    match script {
+        Some(script) => {
+            let script = BufReader::new(fs::File::open(script)?);
+            debugger.repl(script, io::stdout(), false)?;
+        }
+        None => debugger.repl(io::stdin().lock(), io::stdout(), true)?,
+    }
+
+    Ok(())
+}
+
+fn trace(cli: &Cli, program: Option<&PathBuf>, format: TraceFormat) -> Result<()> {
+    let trace = Trace::record(&load_program(cli, program)?);
+
+    match format {
+        TraceFormat::Csv => print!("{}", trace.to_csv()),
+        TraceFormat::Json => println!("{}", trace.to_json()?),
+        TraceFormat::Report => match trace.loop_report() {
+            Some(report) => print!("{}", report),
+            None => println!("no infinite loop: {}", trace.halt),
+        },
+    }
+
+    Ok(())
+}
+
+fn load_rules(cli: &Cli, rules: Option<&PathBuf>) -> Result<BagGraph> {
+    let path = rules.cloned().unwrap_or_else(|| cli.input.join("day7.txt"));
+
+    Ok(day07::load(
+        fs::read_to_string(path)?.trim_end_matches('\n'),
+    )?)
+}
+
+fn colour(graph: &BagGraph, name: &str) -> Result<Colour> {
+    graph
+        .id(name)
+        .ok_or_else(|| anyhow!("no {} bags in the rules", name))
+}
+
+fn bag_graph(
+    cli: &Cli,
+    rules: Option<&PathBuf>,
+    from: Option<&str>,
+    to: Option<&str>,
+    mermaid: bool,
+) -> Result<()> {
+    let graph = load_rules(cli, rules)?;
+    let subgraph = match (from, to) {
+        (Some(name), _) => Subgraph::From(colour(&graph, name)?),
+        (None, Some(name)) => Subgraph::To(colour(&graph, name)?),
+        (None, None) => Subgraph::All,
+    };
+
+    if mermaid {
+        print!("{}", graph.to_mermaid(subgraph));
+    } else {
+        print!("{}", graph.to_dot(subgraph));
+    }
+
+    Ok(())
+}
+
+fn bags(cli: &Cli, rules: Option<&PathBuf>, query: &BagQuery) -> Result<()> {
+    let graph = load_rules(cli, rules)?;
+    let names = |colours: &[Colour]| -> Vec<_> {
+        colours
+            .iter()
+            .map(|&colour| graph.name(colour).to_owned())
+            .collect()
+    };
+
+    match query {
+        BagQuery::Containers { colour } => {
+            let mut containers = names(&graph.containing(self::colour(&graph, colour)?));
+            containers.sort_unstable();
+            for container in containers {
+                println!("{}", container);
+            }
+        }
+        BagQuery::Count { colour } => {
+            println!("{}", graph.count_contents(self::colour(&graph, colour)?)?)
+        }
+        BagQuery::Chain { from, to, longest } => {
+            let (from, to) = (self::colour(&graph, from)?, self::colour(&graph, to)?);
+            let chain = if *longest {
+                graph.longest_chain(from, to)?
+            } else {
+                graph.shortest_chain(from, to)
+            };
+
+            match chain {
+                Some(chain) => println!("{}", names(&chain).join(" -> ")),
+                None => println!("no chain"),
+            }
+        }
+        BagQuery::Depth { colour } => println!("{}", graph.depth(self::colour(&graph, colour)?)?),
+        BagQuery::Bom { colour } => {
+            for (inner, quantity) in graph.bill_of_materials(self::colour(&graph, colour)?)? {
+                println!("{:>8} {}", quantity, graph.name(inner));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        None | Some(Command::Run) => run(&cli)?,
+        Some(Command::Verify { answers, record }) => {
+            if !verify(&cli, answers.as_ref(), *record)? {
+                process::exit(1);
+            }
+        }
+        Some(Command::Expenses {
+            report,
+            count,
+            target,
+            all,
+        }) => expenses(&cli, report.as_ref(), *count, *target, *all)?,
+        Some(Command::Passwords {
+            database,
+            policies,
+            report,
+            failures,
+        }) => passwords(&cli, database.as_ref(), policies, *report, *failures)?,
+        Some(Command::Slopes {
+            map,
+            slopes,
+            max_right,
+            max_down,
+            line,
+        }) => self::slopes(&cli, map.as_ref(), slopes, *max_right, *max_down, *line)?,
+        Some(Command::Ride {
+            map,
+            slope,
+            line,
+            svg,
+        }) => ride(&cli, map.as_ref(), *slope, *line, *svg)?,
+        Some(Command::Cfg { program, mermaid }) => cfg(&cli, program.as_ref(), *mermaid)?,
+        Some(Command::Debug { program, script }) => debug(&cli, program.as_ref(), script.as_ref())?,
+        Some(Command::Trace { program, format }) => trace(&cli, program.as_ref(), *format)?,
+        Some(Command::BagGraph {
+            rules,
+            from,
+            to,
+            mermaid,
+        }) => bag_graph(
+            &cli,
+            rules.as_ref(),
+            from.as_deref(),
+            to.as_deref(),
+            *mermaid,
+        )?,
+        Some(Command::Bags { rules, query }) => bags(&cli, rules.as_ref(), query)?,
+    }
+
+    Ok(())
+}
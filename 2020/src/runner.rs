@@ -0,0 +1,208 @@
+use crate::{answer::Answer, *};
+use serde::Serialize;
+use std::{
+    borrow::Borrow,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub day: u32,
+    pub part: u32,
+    pub answer: Answer,
+    pub generator: Duration,
+    pub solver: Duration,
+}
+
+/// A machine-readable summary of an outcome, for `--json` output.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Record<'a> {
+    pub day: u32,
+    pub part: Option<u32>,
+    pub answer: Option<&'a Answer>,
+    pub generator_ns: Option<u128>,
+    pub solver_ns: Option<u128>,
+    pub error: Option<&'a str>,
+}
+
+impl Record<'_> {
+    pub fn error(day: u32, error: &str) -> Record<'_> {
+        Record {
+            day,
+            part: None,
+            answer: None,
+            generator_ns: None,
+            solver_ns: None,
+            error: Some(error),
+        }
+    }
+}
+
+impl<'a> From<&'a Outcome> for Record<'a> {
+    fn from(outcome: &'a Outcome) -> Self {
+        let (answer, error) = match &outcome.answer {
+            Answer::Failure(reason) => (None, Some(reason.as_str())),
+            answer => (Some(answer), None),
+        };
+
+        Record {
+            day: outcome.day,
+            part: Some(outcome.part),
+            answer,
+            generator_ns: Some(outcome.generator.as_nanos()),
+            solver_ns: Some(outcome.solver.as_nanos()),
+            error,
+        }
+    }
+}
+
+pub struct Day {
+    pub day: u32,
+    run: fn(&str) -> Vec<Outcome>,
+}
+
+impl Day {
+    pub fn run(&self, input: &str) -> Vec<Outcome> {
+        (self.run)(input)
+    }
+}
+
+/// Generates the input for a day once and runs each of its parts on it, so
+/// parts sharing a `Context` only compute the shared value once.
+pub(crate) fn run<I, B, E>(
+    day: u32,
+    input: &str,
+    generator: fn(&str) -> Result<I, E>,
+    parts: &[fn(&B) -> Answer],
+) -> Vec<Outcome>
+where
+    I: Borrow<B>,
+    B: ?Sized,
+    E: Display,
+{
+    let start_time = Instant::now();
+    let generated = generator(input.trim_end_matches('\n'));
+    let generator_time = start_time.elapsed();
+
+    parts
+        .iter()
+        .zip(1..)
+        .map(|(solver, part)| {
+            let start_time = Instant::now();
+            let answer = match &generated {
+                Ok(generated) => solver(generated.borrow()),
+                Err(e) => Answer::Failure(e.to_string()),
+            };
+
+            Outcome {
+                day,
+                part,
+                answer,
+                generator: generator_time,
+                solver: start_time.elapsed(),
+            }
+        })
+        .collect()
+}
+
+pub static DAYS: &[Day] = &[
+    Day {
+        day: 1,
+        run: day01::run,
+    },
+    Day {
+        day: 2,
+        run: day02::run,
+    },
+    Day {
+        day: 3,
+        run: day03::run,
+    },
+    Day {
+        day: 4,
+        run: day04::run,
+    },
+    Day {
+        day: 5,
+        run: day05::run,
+    },
+    Day {
+        day: 6,
+        run: day06::run,
+    },
+    Day {
+        day: 7,
+        run: day07::run,
+    },
+    Day {
+        day: 8,
+        run: day08::run,
+    },
+    Day {
+        day: 9,
+        run: day09::run,
+    },
+    Day {
+        day: 10,
+        run: day10::run,
+    },
+    Day {
+        day: 11,
+        run: day11::run,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_runs_a_day() {
+        let outcomes = DAYS[0].run("1721\n979\n366\n299\n675\n1456\n");
+
+        assert_eq!(
+            vec![
+                (1, 1, Answer::Integer(514579)),
+                (1, 2, Answer::Integer(241861950))
+            ],
+            outcomes
+                .into_iter()
+                .map(|outcome| (outcome.day, outcome.part, outcome.answer))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_reports_generator_failures() {
+        let outcomes = DAYS[0].run("not a number");
+
+        assert!(outcomes.iter().all(|outcome| outcome.answer.is_failure()));
+    }
+
+    #[test]
+    fn it_serializes_records() {
+        let outcome = Outcome {
+            day: 9,
+            part: 2,
+            answer: Answer::Integer(62),
+            generator: Duration::from_micros(15),
+            solver: Duration::from_nanos(300),
+        };
+
+        assert_eq!(
+            r#"{"day":9,"part":2,"answer":{"type":"integer","value":62},"generator_ns":15000,"solver_ns":300,"error":null}"#,
+            serde_json::to_string(&Record::from(&outcome)).unwrap()
+        );
+
+        let outcome = Outcome {
+            answer: Answer::Failure(String::from("day 9: no contiguous range sums to 127")),
+            ..outcome
+        };
+
+        assert_eq!(
+            r#"{"day":9,"part":2,"answer":null,"generator_ns":15000,"solver_ns":300,"error":"day 9: no contiguous range sums to 127"}"#,
+            serde_json::to_string(&Record::from(&outcome)).unwrap()
+        );
+    }
+}